@@ -0,0 +1,132 @@
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Expr, ExprLit, Ident, Lit, LitStr, Meta, Token};
+
+// The comma separated flags passed as `#[noble(flag, key = "value", ...)]`
+#[derive(Default)]
+pub(crate) struct Args {
+    metas: Vec<Meta>,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let metas = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
+
+        Ok(Self {
+            metas: metas.into_iter().collect(),
+        })
+    }
+}
+
+impl Args {
+    pub(crate) fn flag(&self, name: &str) -> bool {
+        self.metas.iter().any(|meta| meta.path().is_ident(name))
+    }
+
+    // Parses the value given as `name = "..."`, e.g. a type in `transmute_enum = "Other"`;
+    // anything other than a string, like the `8` in `assume_aligned = 8`, is parsed as written
+    pub(crate) fn value<T: Parse>(&self, name: &str) -> syn::Result<Option<T>> {
+        self.metas
+            .iter()
+            .find(|meta| meta.path().is_ident(name))
+            .map(parse_value)
+            .transpose()
+    }
+
+    // The string given as `name = "..."` itself, for values that aren't Rust syntax
+    pub(crate) fn string(&self, name: &str) -> syn::Result<Option<LitStr>> {
+        let Some(meta) = self.metas.iter().find(|meta| meta.path().is_ident(name)) else {
+            return Ok(None);
+        };
+
+        match meta {
+            Meta::NameValue(pair) => match &pair.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(lit), ..
+                }) => Ok(Some(lit.clone())),
+                value => Err(syn::Error::new_spanned(value, "expected a string literal")),
+            },
+            _ => Err(syn::Error::new_spanned(
+                meta,
+                format!("expected `{} = \"...\"`", name),
+            )),
+        }
+    }
+
+    // Every `key = "..."` pair in the list, in order, e.g. the methods in `dispatch(methods(...))`
+    pub(crate) fn pairs<T: Parse>(&self) -> syn::Result<Vec<(Ident, T)>> {
+        self.metas
+            .iter()
+            .map(|meta| Ok((meta.path().require_ident()?.clone(), parse_value(meta)?)))
+            .collect()
+    }
+
+    // The entries as bare identifiers, e.g. the values in `leak_on_panic(handle, buffer)`
+    pub(crate) fn idents(&self) -> syn::Result<Vec<Ident>> {
+        self.metas
+            .iter()
+            .map(|meta| match meta {
+                Meta::Path(path) => path.require_ident().cloned(),
+                _ => Err(syn::Error::new_spanned(meta, "expected an identifier")),
+            })
+            .collect()
+    }
+
+    // The flags nested as `name(...)`; a bare `name` counts as an empty list
+    pub(crate) fn nested(&self, name: &str) -> syn::Result<Option<Args>> {
+        let Some(meta) = self.metas.iter().find(|meta| meta.path().is_ident(name)) else {
+            return Ok(None);
+        };
+
+        match meta {
+            Meta::Path(_) => Ok(Some(Self::default())),
+            Meta::List(list) => list.parse_args().map(Some),
+            Meta::NameValue(_) => Err(syn::Error::new_spanned(
+                meta,
+                format!("expected `{}` or `{}(...)`", name, name),
+            )),
+        }
+    }
+
+    // The same flags minus `name`, for flags handled before the item kind is known
+    pub(crate) fn without(&self, name: &str) -> Args {
+        Self {
+            metas: self
+                .metas
+                .iter()
+                .filter(|meta| !meta.path().is_ident(name))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    // Rejects any flag that the item kind being wrapped doesn't understand
+    pub(crate) fn ensure_known(&self, known: &[&str]) -> syn::Result<()> {
+        for meta in &self.metas {
+            if !known.iter().any(|name| meta.path().is_ident(name)) {
+                return Err(syn::Error::new_spanned(
+                    meta.path(),
+                    "unsupported #[noble] flag for this item",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_value<T: Parse>(meta: &Meta) -> syn::Result<T> {
+    match meta {
+        Meta::NameValue(pair) => match &pair.value {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(lit), ..
+            }) => lit.parse(),
+            value => syn::parse2(value.to_token_stream()),
+        },
+        _ => Err(syn::Error::new_spanned(
+            meta,
+            format!("expected `{} = ...`", meta.path().to_token_stream()),
+        )),
+    }
+}
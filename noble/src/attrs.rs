@@ -0,0 +1,88 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{Attribute, File, Ident, Item, Meta, Path, Token};
+
+// Every hint listed in the item's `#[repr(...)]` attributes, e.g. `u8` or `C`
+pub(crate) fn repr_hints(attrs: &[Attribute]) -> syn::Result<Vec<Ident>> {
+    let mut hints = Vec::new();
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        let nested = attr.parse_args_with(Punctuated::<syn::Meta, Token![,]>::parse_terminated)?;
+        hints.extend(
+            nested
+                .iter()
+                .filter_map(|meta| meta.path().get_ident().cloned()),
+        );
+    }
+
+    Ok(hints)
+}
+
+// The `#[derive(...)]` entry naming `name`, if the item derives it
+pub(crate) fn derives(attrs: &[Attribute], name: &str) -> syn::Result<Option<Path>> {
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("derive")) {
+        let paths = attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)?;
+
+        if let Some(path) = paths.into_iter().find(|path| {
+            path.segments
+                .last()
+                .is_some_and(|segment| segment.ident == name)
+        }) {
+            return Ok(Some(path));
+        }
+    }
+
+    Ok(None)
+}
+
+const INTEGER_REPRS: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+// The primitive named by `#[repr(u8)]` and friends, if any
+pub(crate) fn int_repr(attrs: &[Attribute]) -> syn::Result<Option<Ident>> {
+    Ok(repr_hints(attrs)?
+        .into_iter()
+        .find(|hint| INTEGER_REPRS.iter().any(|repr| hint == repr)))
+}
+
+// Whether the item carries `#[doc(hidden)]`
+pub(crate) fn doc_hidden(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc") && matches!(attr.meta, Meta::List(_)))
+        .any(|attr| {
+            attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+                .is_ok_and(|nested| nested.iter().any(|meta| meta.path().is_ident("hidden")))
+        })
+}
+
+// Puts `#[doc(hidden)]` on every impl block among the generated items
+pub(crate) fn hide_impls(generated: TokenStream) -> syn::Result<TokenStream> {
+    let mut file: File = syn::parse2(generated)?;
+
+    for item in &mut file.items {
+        if let Item::Impl(impl_item) = item {
+            impl_item.attrs.push(syn::parse_quote! { #[doc(hidden)] });
+        }
+    }
+
+    let items = &file.items;
+    Ok(quote! { #(#items)* })
+}
+
+const SINGLE_USE: &[&str] = &["export_name", "no_mangle", "link_section", "test"];
+
+// Whether the attribute exports a symbol or registers the item, e.g. `#[no_mangle]` or
+// `#[test]`, which two copies of the item can't both do. Looks inside `#[unsafe(...)]`
+pub(crate) fn single_use(attr: &Attribute) -> bool {
+    let names = |path: &Path| SINGLE_USE.iter().any(|name| path.is_ident(name));
+
+    match &attr.meta {
+        Meta::List(list) if list.path.is_ident("unsafe") => list
+            .parse_args::<Meta>()
+            .is_ok_and(|inner| names(inner.path())),
+        meta => names(meta.path()),
+    }
+}
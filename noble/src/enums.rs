@@ -0,0 +1,166 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Fields, ItemEnum, Type};
+
+use crate::args::Args;
+use crate::{attrs, structs};
+
+pub(crate) const FLAGS: &[&str] = &[
+    "transmute_enum",
+    "discriminant",
+    "from_discriminant",
+    "hide_generated",
+    "extract",
+];
+
+// Extra unsafe helpers requested through `#[noble(...)]` flags on an enum
+pub(crate) fn helpers(args: &Args, item: &ItemEnum) -> syn::Result<TokenStream> {
+    let mut methods = Vec::new();
+
+    if let Some(target) = args.value::<Type>("transmute_enum")? {
+        methods.push(transmute_enum(item, &target)?);
+    }
+
+    if args.flag("discriminant") {
+        methods.push(discriminant(item)?);
+    }
+
+    if args.flag("from_discriminant") {
+        methods.push(from_discriminant(item)?);
+    }
+
+    if args.flag("extract") {
+        methods.push(extract(item)?);
+    }
+
+    if methods.is_empty() {
+        return Ok(TokenStream::new());
+    }
+
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            #(#methods)*
+        }
+    })
+}
+
+fn transmute_enum(item: &ItemEnum, target: &Type) -> syn::Result<TokenStream> {
+    // The default representation has no layout guarantees at all, not even between two
+    // identically declared enums, so only an explicit repr gets past here.
+    if attrs::repr_hints(&item.attrs)?.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(transmute_enum)] needs an explicit #[repr(...)] on the enum",
+        ));
+    }
+
+    Ok(quote! {
+        /// Reinterprets this value as the mirrored enum; matching discriminants are on you.
+        pub unsafe fn as_other(self) -> #target {
+            unsafe { ::core::mem::transmute::<Self, #target>(self) }
+        }
+    })
+}
+
+fn discriminant(item: &ItemEnum) -> syn::Result<TokenStream> {
+    let Some(repr) = attrs::int_repr(&item.attrs)? else {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(discriminant)] needs an integer repr such as #[repr(u8)]",
+        ));
+    };
+
+    Ok(quote! {
+        /// Reads the tag straight out of the first bytes of the value.
+        pub unsafe fn discriminant(&self) -> #repr {
+            unsafe { *(self as *const Self as *const #repr) }
+        }
+    })
+}
+
+fn from_discriminant(item: &ItemEnum) -> syn::Result<TokenStream> {
+    let Some(repr) = attrs::int_repr(&item.attrs)? else {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(from_discriminant)] needs an integer repr such as #[repr(u8)]",
+        ));
+    };
+
+    // With fields the enum is bigger than its tag, and there would be nothing to put in them
+    if let Some(variant) = item
+        .variants
+        .iter()
+        .find(|variant| !matches!(variant.fields, Fields::Unit))
+    {
+        return Err(syn::Error::new_spanned(
+            variant,
+            "#[noble(from_discriminant)] only supports fieldless enums",
+        ));
+    }
+
+    Ok(quote! {
+        /// Turns a tag back into a variant; a value no variant declares is UB.
+        pub unsafe fn from_discriminant(discriminant: #repr) -> Self {
+            unsafe { ::core::mem::transmute::<#repr, Self>(discriminant) }
+        }
+    })
+}
+
+// `into_<variant>_unchecked(self)` per variant with fields, moving the payload out and
+// telling the optimizer every other variant is impossible
+fn extract(item: &ItemEnum) -> syn::Result<TokenStream> {
+    let mut extractors = Vec::new();
+    let others = (item.variants.len() > 1)
+        .then(|| quote! { _ => unsafe { ::core::hint::unreachable_unchecked() }, });
+
+    for variant in &item.variants {
+        if variant.fields.is_empty() {
+            continue;
+        }
+
+        let ident = &variant.ident;
+        let method = format_ident!("into_{}_unchecked", structs::snake_case(ident));
+        let bindings: Vec<_> = (0..variant.fields.len())
+            .map(|index| format_ident!("field_{}", index))
+            .collect();
+        let types: Vec<_> = variant.fields.iter().map(|field| &field.ty).collect();
+
+        let pattern = match &variant.fields {
+            Fields::Named(fields) => {
+                let names = fields.named.iter().map(|field| &field.ident);
+                quote! { Self::#ident { #(#names: #bindings),* } }
+            }
+            _ => quote! { Self::#ident(#(#bindings),*) },
+        };
+        let (output, value) = match (types.as_slice(), bindings.as_slice()) {
+            ([ty], [binding]) => (quote! { #ty }, quote! { #binding }),
+            _ => (quote! { (#(#types,)*) }, quote! { (#(#bindings,)*) }),
+        };
+        let doc = format!(
+            "Moves the payload out of `{}`, which this value must be; anything else is UB.",
+            ident
+        );
+
+        extractors.push(quote! {
+            #[doc = #doc]
+            pub unsafe fn #method(self) -> #output {
+                match self {
+                    #pattern => #value,
+                    #others
+                }
+            }
+        });
+    }
+
+    if extractors.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(extract)] needs at least one variant with fields",
+        ));
+    }
+
+    Ok(quote! { #(#extractors)* })
+}
@@ -0,0 +1,27 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::Expr;
+
+// Wraps the code that actually runs, so async blocks and closures keep their
+// outer `async`/`move` and only their bodies become unsafe
+pub(crate) fn wrap(expr: Expr) -> TokenStream {
+    match expr {
+        Expr::Async(mut async_block) => {
+            let original_block = &async_block.block;
+            async_block.block = syn::parse_quote! {
+                {
+                    unsafe #original_block
+                }
+            };
+
+            quote! { #async_block }
+        }
+        Expr::Closure(mut closure) => {
+            let body = wrap((*closure.body).clone());
+            *closure.body = syn::parse_quote! { { #body } };
+
+            quote! { #closure }
+        }
+        expr => quote! { unsafe { #expr } },
+    }
+}
@@ -0,0 +1,806 @@
+use std::collections::HashSet;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::visit::Visit;
+use syn::visit_mut::VisitMut;
+use syn::{
+    BinOp, Expr, ExprLit, FnArg, Ident, Item, ItemFn, ItemMod, Lit, Pat, ReturnType, Type,
+    Visibility,
+};
+
+use crate::args::Args;
+use crate::attrs;
+use crate::ty;
+
+pub(crate) const FLAGS: &[&str] = &[
+    "gen_checked",
+    "catch_all",
+    "emit_marker",
+    "prefetch",
+    "memoize",
+    "unchecked_arith",
+    "cache_through_shared",
+    "ffi_result",
+    "abort_on_err",
+    "intrinsics",
+    "panic_to_errno",
+    "catch_and_return",
+    "timed",
+    "leak_on_panic",
+];
+
+// Moves the wrapped function to `<name>_unsafe` and puts a safe `<name>` in front of it
+pub(crate) fn gen_checked(func: ItemFn) -> syn::Result<TokenStream> {
+    let name = &func.sig.ident;
+    let unsafe_name = format_ident!("{}_unsafe", name);
+
+    let mut checked_sig = func.sig.clone();
+    let mut forwarded = Vec::new();
+
+    for (index, input) in checked_sig.inputs.iter_mut().enumerate() {
+        let FnArg::Typed(pat_type) = input else {
+            return Err(syn::Error::new_spanned(
+                input,
+                "#[noble(gen_checked)] only supports free functions",
+            ));
+        };
+
+        // Parameters can be arbitrary patterns, so the safe side gets plain names to forward
+        let arg = format_ident!("arg{}", index);
+        *pat_type.pat = syn::parse_quote! { #arg };
+        forwarded.push(arg);
+    }
+
+    let await_call = checked_sig.asyncness.map(|_| quote! { .await });
+    let checked_attrs = &func.attrs;
+    let vis = &func.vis;
+    let doc = format!(
+        "Safe entry point for [`{}`]. noble cannot know its invariants, so this only calls it.",
+        unsafe_name
+    );
+
+    // `cfg`, `inline` and the like apply to both halves, while the docs describe the safe one
+    // and exported symbols or `#[test]` can only be on one
+    let mut unchecked = func.clone();
+    unchecked
+        .attrs
+        .retain(|attr| !attr.path().is_ident("doc") && !attrs::single_use(attr));
+    unchecked
+        .attrs
+        .push(syn::parse_quote! { #[doc = "The unchecked implementation."] });
+    unchecked.sig.ident = unsafe_name.clone();
+    unchecked.sig.unsafety = Some(Default::default());
+
+    Ok(quote! {
+        #unchecked
+
+        #(#checked_attrs)*
+        #[doc = #doc]
+        #vis #checked_sig {
+            unsafe { #unsafe_name(#(#forwarded),*) #await_call }
+        }
+    })
+}
+
+// Runs the already wrapped body under `catch_unwind`, so no panic unwinds out of the function.
+// A panic returns `fallback`, from `catch_and_return = "expr"`, or else `Default::default()`.
+pub(crate) fn catch_all(func: &mut ItemFn, fallback: Option<&Expr>) -> syn::Result<()> {
+    let flag = match fallback {
+        Some(_) => "catch_and_return",
+        None => "catch_all",
+    };
+
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &func.sig.ident,
+            format!("#[noble({})] needs noble's `std` feature", flag),
+        ));
+    }
+
+    if let Some(asyncness) = func.sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            format!("#[noble({})] can't catch panics across an await", flag),
+        ));
+    }
+
+    let name = func.sig.ident.to_string();
+    let body = &func.block;
+    let message = panic_message();
+    let (returning, value) = match fallback {
+        Some(fallback) => ("the fallback", quote! { #fallback }),
+        None => ("the default", quote! { ::std::default::Default::default() }),
+    };
+
+    func.block = syn::parse_quote! {
+        {
+            match ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(move || #body)) {
+                ::std::result::Result::Ok(value) => value,
+                ::std::result::Result::Err(payload) => {
+                    let message = #message;
+                    ::std::eprintln!("{} panicked, returning {}: {}", #name, #returning, message);
+
+                    #value
+                }
+            }
+        }
+    };
+
+    Ok(())
+}
+
+// Runs the body under `catch_unwind` borrowing its arguments, and on a panic forgets the
+// named ones before resuming the unwind, so their destructors never run on that path
+pub(crate) fn leak_on_panic(func: &mut ItemFn, leaked: &[Ident]) -> syn::Result<()> {
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &func.sig.ident,
+            "#[noble(leak_on_panic)] needs noble's `std` feature",
+        ));
+    }
+
+    if let Some(asyncness) = func.sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "#[noble(leak_on_panic)] can't catch panics across an await",
+        ));
+    }
+
+    if leaked.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &func.sig.ident,
+            "#[noble(leak_on_panic)] needs the arguments to leak, e.g. `leak_on_panic(handle)`",
+        ));
+    }
+
+    for ident in leaked {
+        let is_param = func.sig.inputs.iter().any(|input| match input {
+            FnArg::Receiver(_) => ident == "self",
+            FnArg::Typed(pat_type) => {
+                matches!(&*pat_type.pat, Pat::Ident(pat) if pat.ident == *ident)
+            }
+        });
+
+        if !is_param {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "#[noble(leak_on_panic)] can only leak the function's own arguments",
+            ));
+        }
+    }
+
+    let body = &func.block;
+
+    // The closure only borrows, so the arguments are still here to forget afterwards
+    func.block = syn::parse_quote! {
+        {
+            match ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(|| #body)) {
+                ::std::result::Result::Ok(value) => value,
+                ::std::result::Result::Err(payload) => {
+                    #(::core::mem::forget(#leaked);)*
+                    ::std::panic::resume_unwind(payload)
+                }
+            }
+        }
+    };
+
+    Ok(())
+}
+
+// The `&str` inside a caught panic's `payload`, which is what `panic!` almost always carries
+fn panic_message() -> TokenStream {
+    quote! {
+        payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<::std::string::String>().map(|s| s.as_str()))
+            .unwrap_or("non-string panic payload")
+    }
+}
+
+// Like `catch_all`, but the panic message is kept in a thread-local for C to fetch through
+// `<name>_last_error()`, errno style. Returns that companion function, unless the panic goes
+// to the `shared` store of an enclosing `#[noble(panic_to_errno)] mod`.
+pub(crate) fn panic_to_errno(
+    func: &mut ItemFn,
+    shared: Option<&Ident>,
+) -> syn::Result<Option<TokenStream>> {
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &func.sig.ident,
+            "#[noble(panic_to_errno)] needs noble's `std` feature",
+        ));
+    }
+
+    if let Some(asyncness) = func.sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "#[noble(panic_to_errno)] can't catch panics across an await",
+        ));
+    }
+
+    let stem = func.sig.ident.unraw();
+    let storage = match shared {
+        Some(storage) => storage.clone(),
+        None => format_ident!("_NOBLE_{}_LAST_ERROR", stem.to_string().to_uppercase()),
+    };
+    let body = &func.block;
+    let message = panic_message();
+
+    func.block = syn::parse_quote! {
+        {
+            match ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(move || #body)) {
+                ::std::result::Result::Ok(value) => value,
+                ::std::result::Result::Err(payload) => {
+                    let message = #message;
+                    let message = ::std::ffi::CString::new(message.replace('\0', ""))
+                        .unwrap_or_default();
+                    #storage.with(|last| *last.borrow_mut() = ::core::option::Option::Some(message));
+
+                    ::std::default::Default::default()
+                }
+            }
+        }
+    };
+
+    if shared.is_some() {
+        return Ok(None);
+    }
+
+    let doc = format!(
+        "The message of the last panic caught in [`{}`] on this thread, or null. The pointer \
+         is valid until the next one.",
+        stem
+    );
+
+    Ok(Some(errno_store(
+        &func.vis,
+        &storage,
+        &format_ident!("{}_last_error", stem),
+        &doc,
+    )))
+}
+
+// The one store and `last_error()` shared by every function in a `#[noble(panic_to_errno)] mod`
+pub(crate) fn shared_errno_store(module: &ItemMod, storage: &Ident) -> syn::Result<TokenStream> {
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &module.ident,
+            "#[noble(panic_to_errno)] needs noble's `std` feature",
+        ));
+    }
+
+    let vis = syn::parse_quote! { pub };
+    let doc = "The message of the last panic caught in any function of this module on this \
+               thread, or null. The pointer is valid until the next one.";
+
+    Ok(errno_store(
+        &vis,
+        storage,
+        &format_ident!("last_error"),
+        doc,
+    ))
+}
+
+fn errno_store(vis: &Visibility, storage: &Ident, last_error: &Ident, doc: &str) -> TokenStream {
+    quote! {
+        ::std::thread_local! {
+            #[doc(hidden)]
+            static #storage: ::core::cell::RefCell<::core::option::Option<::std::ffi::CString>> =
+                const { ::core::cell::RefCell::new(::core::option::Option::None) };
+        }
+
+        #[doc = #doc]
+        #vis unsafe fn #last_error() -> *const ::core::ffi::c_char {
+            #storage.with(|last| match &*last.borrow() {
+                ::core::option::Option::Some(message) => message.as_ptr(),
+                ::core::option::Option::None => ::core::ptr::null(),
+            })
+        }
+    }
+}
+
+// `_NOBLE_WRAPPED_<NAME>`, for tests that want proof the attribute ran
+pub(crate) fn marker(func: &ItemFn) -> TokenStream {
+    let vis = &func.vis;
+    let name = format_ident!(
+        "_NOBLE_WRAPPED_{}",
+        func.sig.ident.unraw().to_string().to_uppercase()
+    );
+
+    quote! {
+        #[doc(hidden)]
+        #vis const #name: bool = true;
+    }
+}
+
+// Hints every raw pointer parameter into cache before the body runs. The caller needs
+// `#![feature(core_intrinsics)]`
+pub(crate) fn prefetch(func: &mut ItemFn) -> syn::Result<()> {
+    if !cfg!(feature = "nightly") {
+        return Err(syn::Error::new_spanned(
+            &func.sig.ident,
+            "#[noble(prefetch)] needs noble's `nightly` feature",
+        ));
+    }
+
+    let pointers: Vec<_> = func
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|input| match input {
+            FnArg::Typed(pat_type) => match &*pat_type.pat {
+                Pat::Ident(pat) if ty::pointee(&pat_type.ty).is_some() => Some(&pat.ident),
+                _ => None,
+            },
+            FnArg::Receiver(_) => None,
+        })
+        .collect();
+
+    if pointers.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &func.sig,
+            "#[noble(prefetch)] needs a `*const T` or `*mut T` parameter",
+        ));
+    }
+
+    // Locality 3 asks to keep the data in every level of cache
+    let hints: Vec<syn::Stmt> = pointers
+        .iter()
+        .map(|pointer| syn::parse_quote! { ::core::intrinsics::prefetch_read_data::<_, 3>(#pointer); })
+        .collect();
+    func.block.stmts.splice(0..0, hints);
+
+    Ok(())
+}
+
+// Starts the body with a guard that prints the elapsed time when dropped, so early returns
+// and unwinds are timed as well
+pub(crate) fn timed(func: &mut ItemFn) -> syn::Result<()> {
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &func.sig.ident,
+            "#[noble(timed)] needs noble's `std` feature",
+        ));
+    }
+
+    let name = func.sig.ident.unraw().to_string();
+
+    func.block.stmts.insert(
+        0,
+        syn::parse_quote! {
+            let _noble_timer = {
+                struct Timer(::std::time::Instant);
+
+                impl ::core::ops::Drop for Timer {
+                    fn drop(&mut self) {
+                        ::std::eprintln!("{} took {:?}", #name, self.0.elapsed());
+                    }
+                }
+
+                Timer(::std::time::Instant::now())
+            };
+        },
+    );
+
+    Ok(())
+}
+
+// Brings `core::intrinsics` into scope at the top of the body, so calls read as
+// `intrinsics::assume(..)`. An attribute can't enable features, the caller still needs
+// `#![feature(core_intrinsics)]`
+pub(crate) fn intrinsics(func: &mut ItemFn) -> syn::Result<()> {
+    if !cfg!(feature = "nightly") {
+        return Err(syn::Error::new_spanned(
+            &func.sig.ident,
+            "#[noble(intrinsics)] needs noble's `nightly` feature",
+        ));
+    }
+
+    func.block.stmts.insert(
+        0,
+        syn::parse_quote! {
+            #[allow(unused_imports)]
+            use ::core::intrinsics;
+        },
+    );
+
+    Ok(())
+}
+
+// Caches results in an unsynchronized `static mut` keyed by the arguments; calling it
+// from two threads at once is a data race
+pub(crate) fn memoize(func: &mut ItemFn) -> syn::Result<()> {
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &func.sig.ident,
+            "#[noble(memoize)] needs noble's `std` feature",
+        ));
+    }
+
+    // The cache is a `static`, which can't mention generics or unnameable types
+    if !func.sig.generics.params.is_empty() || func.sig.asyncness.is_some() {
+        return Err(syn::Error::new_spanned(
+            &func.sig,
+            "#[noble(memoize)] doesn't support generic or async functions",
+        ));
+    }
+
+    let mut args = Vec::new();
+    let mut types = Vec::new();
+    let mut bindings = Vec::new();
+
+    for (index, input) in func.sig.inputs.iter_mut().enumerate() {
+        let FnArg::Typed(pat_type) = input else {
+            return Err(syn::Error::new_spanned(
+                input,
+                "#[noble(memoize)] only supports free functions",
+            ));
+        };
+        if let Type::ImplTrait(_) = &*pat_type.ty {
+            return Err(syn::Error::new_spanned(
+                &pat_type.ty,
+                "#[noble(memoize)] needs nameable argument types",
+            ));
+        }
+
+        // Parameters can be arbitrary patterns, so they are taken whole and destructured later
+        let arg = format_ident!("arg{}", index);
+        let pat = std::mem::replace(&mut *pat_type.pat, syn::parse_quote! { #arg });
+        bindings.push(quote! { let #pat = #arg; });
+        types.push(pat_type.ty.clone());
+        args.push(arg);
+    }
+
+    let output = match &func.sig.output {
+        ReturnType::Default => quote! { () },
+        ReturnType::Type(_, ty) => quote! { #ty },
+    };
+    let body = &func.block;
+
+    // The cache is only borrowed around each lookup, so a recursive call can't alias it
+    func.block = syn::parse_quote! {
+        {
+            static mut CACHE: ::core::option::Option<
+                ::std::collections::HashMap<(#(#types,)*), #output>,
+            > = ::core::option::Option::None;
+
+            let key = (#(::core::clone::Clone::clone(&#args),)*);
+            if let ::core::option::Option::Some(hit) = (*&raw mut CACHE)
+                .get_or_insert_with(::std::collections::HashMap::new)
+                .get(&key)
+            {
+                return ::core::clone::Clone::clone(hit);
+            }
+
+            let value = (move || -> #output {
+                #(#bindings)*
+                #body
+            })();
+            (*&raw mut CACHE)
+                .get_or_insert_with(::std::collections::HashMap::new)
+                .insert(key, ::core::clone::Clone::clone(&value));
+
+            value
+        }
+    };
+
+    Ok(())
+}
+
+// Rewrites `+`, `-` and `*` into `unchecked_add` and friends, but only where an operand is
+// provably a primitive integer: a suffixed literal like `1u32`, or a parameter or local whose
+// declared type is one, against a literal or another such operand. Anything else, untyped
+// locals and operands of user types with their own `Add` included, is left as written
+pub(crate) fn unchecked_arith(func: &mut ItemFn) {
+    let mut rewriter = UncheckedArith::default();
+
+    for input in &func.sig.inputs {
+        if let FnArg::Typed(pat_type) = input {
+            rewriter.bind(&pat_type.pat, Some(&pat_type.ty));
+        }
+    }
+
+    rewriter.visit_block_mut(&mut func.block);
+}
+
+const PRIMITIVE_INTEGERS: &[&str] = &[
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+// The names currently known to hold primitive integers. A binding that shadows one without an
+// integer annotation forgets it, and every block, closure, arm, loop and `if` gets its own
+// scope, so nothing it binds or forgets outlives it
+#[derive(Default)]
+struct UncheckedArith {
+    integers: HashSet<Ident>,
+}
+
+impl UncheckedArith {
+    fn bind(&mut self, pat: &Pat, ty: Option<&Type>) {
+        let is_integer = ty
+            .and_then(ty::path_of)
+            .and_then(|path| path.get_ident())
+            .is_some_and(|ident| PRIMITIVE_INTEGERS.iter().any(|name| ident == name));
+
+        match pat {
+            Pat::Ident(pat) if is_integer && pat.subpat.is_none() => {
+                self.integers.insert(pat.ident.clone());
+            }
+            Pat::Type(pat_type) => self.bind(&pat_type.pat, Some(&pat_type.ty)),
+            _ => Forget(&mut self.integers).visit_pat(pat),
+        }
+    }
+
+    fn scoped(&mut self, visit: impl FnOnce(&mut Self)) {
+        let outer = self.integers.clone();
+        visit(self);
+        self.integers = outer;
+    }
+
+    fn operand(&self, expr: &Expr) -> Option<Operand> {
+        match expr {
+            Expr::Path(path) if path.qself.is_none() => path
+                .path
+                .get_ident()
+                .filter(|ident| self.integers.contains(*ident))
+                .map(|_| Operand::Integer),
+            Expr::Lit(ExprLit {
+                lit: Lit::Int(int), ..
+            }) => Some(if int.suffix().is_empty() {
+                Operand::Literal
+            } else {
+                Operand::Integer
+            }),
+            Expr::Paren(paren) => self.operand(&paren.expr),
+            _ => None,
+        }
+    }
+}
+
+impl VisitMut for UncheckedArith {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        syn::visit_mut::visit_expr_mut(self, expr);
+
+        let Expr::Binary(binary) = expr else {
+            return;
+        };
+        let (method, commutative) = match binary.op {
+            BinOp::Add(_) => ("unchecked_add", true),
+            BinOp::Sub(_) => ("unchecked_sub", false),
+            BinOp::Mul(_) => ("unchecked_mul", true),
+            _ => return,
+        };
+
+        let (left, right) = (&*binary.left, &*binary.right);
+        let (receiver, arg) = match (self.operand(left), self.operand(right)) {
+            // A method call on an unsuffixed literal can't infer its type
+            (Some(Operand::Integer), Some(_)) => (left, right),
+            (Some(Operand::Literal), Some(Operand::Integer)) if commutative => (right, left),
+            _ => return,
+        };
+
+        let method = format_ident!("{}", method);
+        *expr = syn::parse_quote! { #receiver.#method(#arg) };
+    }
+
+    // The initializer still sees the old bindings, so it is visited before the pattern
+    fn visit_local_mut(&mut self, local: &mut syn::Local) {
+        if let Some(init) = &mut local.init {
+            self.visit_expr_mut(&mut init.expr);
+            if let Some((_, diverge)) = &mut init.diverge {
+                self.visit_expr_mut(diverge);
+            }
+        }
+
+        self.bind(&local.pat, None);
+    }
+
+    // Closure parameters, match arms, `for` and `if let` patterns all shadow
+    fn visit_pat_mut(&mut self, pat: &mut Pat) {
+        self.bind(pat, None);
+    }
+
+    fn visit_block_mut(&mut self, block: &mut syn::Block) {
+        self.scoped(|this| syn::visit_mut::visit_block_mut(this, block));
+    }
+
+    fn visit_expr_closure_mut(&mut self, closure: &mut syn::ExprClosure) {
+        self.scoped(|this| syn::visit_mut::visit_expr_closure_mut(this, closure));
+    }
+
+    fn visit_arm_mut(&mut self, arm: &mut syn::Arm) {
+        self.scoped(|this| syn::visit_mut::visit_arm_mut(this, arm));
+    }
+
+    fn visit_expr_for_loop_mut(&mut self, for_loop: &mut syn::ExprForLoop) {
+        self.scoped(|this| syn::visit_mut::visit_expr_for_loop_mut(this, for_loop));
+    }
+
+    // `if let` and `while let` bindings end with the `if` or the loop
+    fn visit_expr_if_mut(&mut self, expr_if: &mut syn::ExprIf) {
+        self.scoped(|this| syn::visit_mut::visit_expr_if_mut(this, expr_if));
+    }
+
+    fn visit_expr_while_mut(&mut self, expr_while: &mut syn::ExprWhile) {
+        self.scoped(|this| syn::visit_mut::visit_expr_while_mut(this, expr_while));
+    }
+
+    // Nested items aren't inside the unsafe block, so they are left alone
+    fn visit_item_mut(&mut self, _: &mut Item) {}
+}
+
+// Drops every name a pattern binds from the known integers
+struct Forget<'a>(&'a mut HashSet<Ident>);
+
+impl<'ast> Visit<'ast> for Forget<'_> {
+    fn visit_pat_ident(&mut self, pat: &'ast syn::PatIdent) {
+        self.0.remove(&pat.ident);
+        syn::visit::visit_pat_ident(self, pat);
+    }
+}
+
+// What an arithmetic operand is known to be: a primitive integer, or an unsuffixed integer
+// literal that takes its type from the other side
+enum Operand {
+    Integer,
+    Literal,
+}
+
+// For a `&self` method: returns the value cached in `self.<field>: Option<T>` if there is one,
+// otherwise runs the body and stores its result there by writing through the shared borrow.
+// Without an `UnsafeCell` that write is UB; the compiler may assume the field never changes
+pub(crate) fn cache_through_shared(func: &mut ItemFn, options: &Args) -> syn::Result<()> {
+    let field = options
+        .value::<Ident>("field")?
+        .unwrap_or_else(|| format_ident!("cache"));
+
+    let takes_shared_self = func.sig.receiver().is_some_and(|receiver| {
+        receiver.reference.is_some()
+            && receiver.mutability.is_none()
+            && receiver.colon_token.is_none()
+    });
+    if !takes_shared_self || func.sig.asyncness.is_some() {
+        return Err(syn::Error::new_spanned(
+            &func.sig,
+            "#[noble(cache_through_shared)] needs a non-async `&self` method",
+        ));
+    }
+
+    let ReturnType::Type(_, output) = &func.sig.output else {
+        return Err(syn::Error::new_spanned(
+            &func.sig,
+            "#[noble(cache_through_shared)] needs a return value to cache",
+        ));
+    };
+    let body = &func.block;
+
+    func.block = syn::parse_quote! {
+        {
+            let slot = ::core::ptr::addr_of!(self.#field).cast_mut();
+            if let ::core::option::Option::Some(hit) = &*slot {
+                return ::core::clone::Clone::clone(hit);
+            }
+
+            let value: #output = (|| #body)();
+            *slot = ::core::option::Option::Some(::core::clone::Clone::clone(&value));
+
+            value
+        }
+    };
+
+    Ok(())
+}
+
+// `Result<(), E>` becomes a C status code: `Ok` is 0, and `Err` is `E`'s `Into<i32>` code
+// made negative, with 0 mapped to -1 so an error never reads as success
+pub(crate) fn ffi_result(func: &mut ItemFn) -> syn::Result<()> {
+    let ReturnType::Type(_, output) = &func.sig.output else {
+        return Err(syn::Error::new_spanned(
+            &func.sig,
+            "#[noble(ffi_result)] needs a `Result<(), E>` return type",
+        ));
+    };
+    let result = (**output).clone();
+
+    match ty::type_args(&result, "Result").as_deref() {
+        Some([Type::Tuple(unit), _]) if unit.elems.is_empty() => {}
+        _ => {
+            return Err(syn::Error::new_spanned(
+                output,
+                "#[noble(ffi_result)] needs a `Result<(), E>` return type",
+            ));
+        }
+    }
+
+    if func.sig.asyncness.is_some() {
+        return Err(syn::Error::new_spanned(
+            &func.sig,
+            "#[noble(ffi_result)] can't return a status code from an async function",
+        ));
+    }
+
+    if func.sig.abi.is_none() {
+        func.sig.abi = Some(syn::parse_quote! { extern "C" });
+    }
+    func.sig.output = syn::parse_quote! { -> i32 };
+    let body = &func.block;
+
+    func.block = syn::parse_quote! {
+        {
+            let result: #result = (move || #body)();
+
+            match result {
+                ::core::result::Result::Ok(()) => 0,
+                ::core::result::Result::Err(error) => {
+                    let code: i32 = ::core::convert::Into::into(error);
+                    match code {
+                        1.. => -code,
+                        0 => -1,
+                        _ => code,
+                    }
+                }
+            }
+        }
+    };
+
+    Ok(())
+}
+
+// Unwraps the body's `Result` in place, aborting the whole process on `Err` so nothing else
+// ever observes the failure
+pub(crate) fn abort_on_err(func: &mut ItemFn) -> syn::Result<()> {
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &func.sig.ident,
+            "#[noble(abort_on_err)] needs noble's `std` feature",
+        ));
+    }
+
+    let ReturnType::Type(_, output) = &func.sig.output else {
+        return Err(syn::Error::new_spanned(
+            &func.sig,
+            "#[noble(abort_on_err)] needs a `Result<T, E>` return type",
+        ));
+    };
+    let result = (**output).clone();
+
+    let value = match ty::type_args(&result, "Result").as_deref() {
+        Some([value, _]) => (*value).clone(),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                output,
+                "#[noble(abort_on_err)] needs a `Result<T, E>` return type",
+            ));
+        }
+    };
+
+    if func.sig.asyncness.is_some() {
+        return Err(syn::Error::new_spanned(
+            &func.sig,
+            "#[noble(abort_on_err)] can't abort from inside an async function's future",
+        ));
+    }
+
+    func.sig.output = syn::parse_quote! { -> #value };
+    let name = func.sig.ident.to_string();
+    let body = &func.block;
+
+    func.block = syn::parse_quote! {
+        {
+            let result: #result = (move || #body)();
+
+            match result {
+                ::core::result::Result::Ok(value) => value,
+                ::core::result::Result::Err(_) => {
+                    ::std::eprintln!("{} returned an error, aborting", #name);
+                    ::std::process::abort()
+                }
+            }
+        }
+    };
+
+    Ok(())
+}
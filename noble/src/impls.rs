@@ -0,0 +1,179 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{ToTokens, format_ident, quote};
+use syn::{FnArg, GenericParam, Ident, ImplItem, ItemImpl, Type, Visibility};
+
+pub(crate) const FLAGS: &[&str] = &["paired", "submodule"];
+
+// Keeps every method as written and adds an `unsafe fn <name>_unchecked` twin with the
+// body wrapped
+pub(crate) fn paired(mut impl_item: ItemImpl) -> syn::Result<TokenStream> {
+    if let Some((_, path, _)) = &impl_item.trait_ {
+        return Err(syn::Error::new_spanned(
+            path,
+            "#[noble(paired)] is for inherent impls, a trait impl can't grow extra methods",
+        ));
+    }
+
+    let mut twins = Vec::new();
+
+    for item in &impl_item.items {
+        let ImplItem::Fn(method) = item else {
+            continue;
+        };
+
+        if method.sig.unsafety.is_some() {
+            continue;
+        }
+
+        let mut twin = method.clone();
+        let original_block = &method.block;
+
+        twin.sig.ident = format_ident!("{}_unchecked", method.sig.ident);
+        twin.sig.unsafety = Some(Default::default());
+        twin.block = syn::parse_quote! {
+            {
+                unsafe #original_block
+            }
+        };
+        twins.push(ImplItem::Fn(twin));
+    }
+
+    impl_item.items.extend(twins);
+
+    Ok(quote! { #impl_item })
+}
+
+// A `mod <name>` of free `unsafe fn`s forwarding to each method, with the receiver as
+// the first argument; the methods themselves stay where they are. Each function reaches as
+// far as its method does, and the module as far as the widest of them
+pub(crate) fn submodule(impl_item: &ItemImpl, module: &Ident) -> syn::Result<TokenStream> {
+    if let Some((_, path, _)) = &impl_item.trait_ {
+        return Err(syn::Error::new_spanned(
+            path,
+            "#[noble(submodule)] is for inherent impls, trait methods already have a namespace",
+        ));
+    }
+
+    let self_ty = &impl_item.self_ty;
+    let mut functions = Vec::new();
+    let mut module_vis = quote! {};
+
+    for item in &impl_item.items {
+        let ImplItem::Fn(method) = item else {
+            continue;
+        };
+        let sig = &method.sig;
+
+        let mut generics = impl_item.generics.clone();
+        generics.params.extend(sig.generics.params.iter().cloned());
+        if let Some(method_where) = &sig.generics.where_clause {
+            generics
+                .make_where_clause()
+                .predicates
+                .extend(method_where.predicates.iter().cloned());
+        }
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let impl_generics = replace_self(impl_generics, self_ty);
+        let where_clause = where_clause.map(|clause| replace_self(clause, self_ty));
+
+        let (params, forwarded): (Vec<_>, Vec<_>) = sig
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| {
+                let (arg, ty) = match input {
+                    FnArg::Receiver(receiver) => (format_ident!("this"), &receiver.ty),
+                    FnArg::Typed(pat_type) => (format_ident!("arg{}", index), &pat_type.ty),
+                };
+                let ty = replace_self(ty, self_ty);
+
+                (quote! { #arg: #ty }, arg)
+            })
+            .unzip();
+
+        let turbofish: Vec<_> = sig
+            .generics
+            .params
+            .iter()
+            .filter_map(|param| match param {
+                GenericParam::Type(param) => Some(&param.ident),
+                GenericParam::Const(param) => Some(&param.ident),
+                GenericParam::Lifetime(_) => None,
+            })
+            .collect();
+        let turbofish = (!turbofish.is_empty()).then(|| quote! { ::<#(#turbofish),*> });
+
+        match &method.vis {
+            Visibility::Public(_) => module_vis = quote! { pub },
+            Visibility::Restricted(_) if module_vis.is_empty() => {
+                module_vis = quote! { pub(crate) }
+            }
+            _ => {}
+        }
+        let vis = nested_visibility(&method.vis);
+
+        let name = &sig.ident;
+        let asyncness = &sig.asyncness;
+        let await_call = asyncness.map(|_| quote! { .await });
+        let output = replace_self(&sig.output, self_ty);
+        let doc = format!(
+            "[`{}::{}`] as a free function.",
+            self_ty.to_token_stream(),
+            name
+        );
+
+        functions.push(quote! {
+            #[doc = #doc]
+            #vis #asyncness unsafe fn #name #impl_generics (#(#params),*) #output #where_clause {
+                unsafe { <#self_ty>::#name #turbofish (#(#forwarded),*) #await_call }
+            }
+        });
+    }
+
+    Ok(quote! {
+        #module_vis mod #module {
+            #[allow(unused_imports)]
+            use super::*;
+
+            #(#functions)*
+        }
+    })
+}
+
+// The same reach as `vis`, written from inside a module one level down
+fn nested_visibility(vis: &Visibility) -> TokenStream {
+    let Visibility::Restricted(restricted) = vis else {
+        return match vis {
+            Visibility::Inherited => quote! { pub(super) },
+            vis => quote! { #vis },
+        };
+    };
+
+    let path = &restricted.path;
+    let mut segments = path.segments.iter();
+    match segments.next() {
+        Some(first) if first.ident == "crate" => quote! { #vis },
+        Some(first) if first.ident == "self" => quote! { pub(in super #(::#segments)*) },
+        _ => quote! { pub(in super::#path) },
+    }
+}
+
+// Free functions have no `Self`, so it's spelled out as the impl's type
+fn replace_self(tokens: impl ToTokens, self_ty: &Type) -> TokenStream {
+    tokens
+        .into_token_stream()
+        .into_iter()
+        .map(|tree| match tree {
+            TokenTree::Ident(ident) if ident == "Self" => self_ty.to_token_stream(),
+            TokenTree::Group(group) => {
+                let mut replaced = proc_macro2::Group::new(
+                    group.delimiter(),
+                    replace_self(group.stream(), self_ty),
+                );
+                replaced.set_span(group.span());
+                TokenTree::Group(replaced).into_token_stream()
+            }
+            tree => tree.into_token_stream(),
+        })
+        .collect()
+}
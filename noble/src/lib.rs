@@ -1,25 +1,126 @@
+#![cfg_attr(
+    feature = "nightly",
+    feature(proc_macro_tracked_env, proc_macro_diagnostic)
+)]
+
+mod args;
+mod attrs;
+mod enums;
+mod exprs;
+mod fns;
+mod impls;
+mod statics;
+mod structs;
+mod traits;
+mod ty;
+
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{Item, ItemEnum, ItemFn, ItemImpl, ItemStruct, ItemTrait, parse_macro_input};
+use quote::{format_ident, quote};
+use syn::{
+    AttrStyle, Expr, Item, ItemEnum, ItemFn, ItemImpl, ItemMod, ItemStatic, ItemStruct, ItemTrait,
+    parse_macro_input,
+};
+
+use args::Args;
 
 #[proc_macro_attribute]
-pub fn noble(_args: TokenStream, input: TokenStream) -> TokenStream {
+pub fn noble(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as Args);
     let item = parse_macro_input!(input as Item);
 
+    gated_item(&args, item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+// `env_gate = "VAR"` leaves the item untouched unless `VAR` is set while it expands
+fn gated_item(args: &Args, item: Item) -> syn::Result<proc_macro2::TokenStream> {
+    let Some(var) = args.string("env_gate")? else {
+        return wrap_item(args, item);
+    };
+
+    if !env_var_set(&var.value()) {
+        return Ok(quote! { #item });
+    }
+
+    wrap_item(&args.without("env_gate"), item)
+}
+
+// On nightly the lookup is tracked, so flipping the variable triggers a rebuild
+#[cfg(feature = "nightly")]
+fn env_var_set(name: &str) -> bool {
+    proc_macro::tracked::env_var(name).is_ok()
+}
+
+#[cfg(not(feature = "nightly"))]
+fn env_var_set(name: &str) -> bool {
+    std::env::var_os(name).is_some()
+}
+
+fn wrap_item(args: &Args, item: Item) -> syn::Result<proc_macro2::TokenStream> {
     match item {
-        Item::Fn(func) => wrap_function(func),
-        Item::Struct(struct_item) => wrap_struct(struct_item),
-        Item::Impl(impl_item) => wrap_impl(impl_item),
-        Item::Enum(enum_item) => wrap_enum(enum_item),
-        Item::Trait(trait_item) => wrap_trait(trait_item),
+        Item::Fn(func) => wrap_function(args, func, None),
+        Item::Struct(struct_item) => wrap_struct(args, struct_item),
+        Item::Impl(impl_item) => wrap_impl(args, impl_item),
+        Item::Enum(enum_item) => wrap_enum(args, enum_item),
+        Item::Trait(trait_item) => wrap_trait(args, trait_item),
+        Item::Static(static_item) => wrap_static(args, static_item),
+        Item::Mod(mod_item) => wrap_mod(args, mod_item),
         _ => {
             // For unsupported items, just return them as-is
-            quote! { #item }.into()
+            Ok(quote! { #item })
         }
     }
 }
 
-fn wrap_function(mut func: ItemFn) -> TokenStream {
+// A function-like macro can't share the attribute's name, hence `noble_expr!` rather than `noble!`
+#[proc_macro]
+pub fn noble_expr(input: TokenStream) -> TokenStream {
+    let expr = parse_macro_input!(input as Expr);
+
+    exprs::wrap(expr).into()
+}
+
+// `errno_store` is the thread-local that an enclosing `#[noble(panic_to_errno)] mod` shares
+fn wrap_function(
+    args: &Args,
+    mut func: ItemFn,
+    errno_store: Option<&syn::Ident>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    args.ensure_known(fns::FLAGS)?;
+
+    if args.flag("prefetch") {
+        fns::prefetch(&mut func)?;
+    }
+
+    if args.flag("intrinsics") {
+        fns::intrinsics(&mut func)?;
+    }
+
+    if args.flag("timed") {
+        fns::timed(&mut func)?;
+    }
+
+    if args.flag("unchecked_arith") {
+        fns::unchecked_arith(&mut func);
+    }
+
+    if args.flag("memoize") {
+        fns::memoize(&mut func)?;
+    }
+
+    if let Some(options) = args.nested("cache_through_shared")? {
+        fns::cache_through_shared(&mut func, &options)?;
+    }
+
+    if args.flag("ffi_result") {
+        fns::ffi_result(&mut func)?;
+    }
+
+    if args.flag("abort_on_err") {
+        fns::abort_on_err(&mut func)?;
+    }
+
     let original_block = &func.block;
 
     func.block = syn::parse_quote! {
@@ -28,20 +129,56 @@ fn wrap_function(mut func: ItemFn) -> TokenStream {
         }
     };
 
-    quote! { #func }.into()
+    if args.flag("catch_all") {
+        fns::catch_all(&mut func, None)?;
+    }
+
+    if let Some(leaked) = args.nested("leak_on_panic")? {
+        fns::leak_on_panic(&mut func, &leaked.idents()?)?;
+    }
+
+    if let Some(fallback) = args.value::<Expr>("catch_and_return")? {
+        fns::catch_all(&mut func, Some(&fallback))?;
+    }
+
+    let last_error = if args.flag("panic_to_errno") {
+        fns::panic_to_errno(&mut func, errno_store)?
+    } else {
+        None
+    };
+
+    let marker = args.flag("emit_marker").then(|| fns::marker(&func));
+
+    if args.flag("gen_checked") {
+        let split = fns::gen_checked(func)?;
+        return Ok(quote! { #split #marker #last_error });
+    }
+
+    Ok(quote! {
+        #func
+        #marker
+        #last_error
+    })
 }
 
-fn wrap_struct(struct_item: ItemStruct) -> TokenStream {
+fn wrap_struct(args: &Args, mut struct_item: ItemStruct) -> syn::Result<proc_macro2::TokenStream> {
+    args.ensure_known(structs::FLAGS)?;
+
+    // Rewrites the fields, so it has to run before anything looks at them
+    let uninit = args
+        .flag("uninit")
+        .then(|| structs::uninit(&mut struct_item));
+    let no_drop = if args.flag("no_drop") {
+        Some(structs::no_drop(&mut struct_item)?)
+    } else {
+        None
+    };
+
     let name = &struct_item.ident;
-    let vis = &struct_item.vis;
-    let attrs = &struct_item.attrs;
     let generics = &struct_item.generics;
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    let original_struct = quote! {
-        #(#attrs)*
-        #vis struct #name #generics #struct_item.fields #where_clause
-    };
+    let original_struct = quote! { #struct_item };
 
     let constructor = match &struct_item.fields {
         syn::Fields::Named(fields) => {
@@ -51,10 +188,8 @@ fn wrap_struct(struct_item: ItemStruct) -> TokenStream {
             quote! {
                 impl #impl_generics #name #ty_generics #where_clause {
                     pub unsafe fn new_unsafe(#(#field_names: #field_types),*) -> Self {
-                        unsafe {
-                            Self {
-                                #(#field_names),*
-                            }
+                        Self {
+                            #(#field_names),*
                         }
                     }
                 }
@@ -70,9 +205,7 @@ fn wrap_struct(struct_item: ItemStruct) -> TokenStream {
             quote! {
                 impl #impl_generics #name #ty_generics #where_clause {
                     pub unsafe fn new_unsafe(#(#param_names: #field_types),*) -> Self {
-                        unsafe {
-                            Self(#(#param_names),*)
-                        }
+                        Self(#(#param_names),*)
                     }
                 }
             }
@@ -81,27 +214,53 @@ fn wrap_struct(struct_item: ItemStruct) -> TokenStream {
             quote! {
                 impl #impl_generics #name #ty_generics #where_clause {
                     pub unsafe fn new_unsafe() -> Self {
-                        unsafe { Self }
+                        Self
                     }
                 }
             }
         }
     };
 
-    quote! {
-        #original_struct
+    let helpers = structs::helpers(args, &struct_item)?;
+
+    let mut generated = quote! {
         #constructor
+        #helpers
+        #uninit
+        #no_drop
+    };
+    if args.flag("hide_generated") || attrs::doc_hidden(&struct_item.attrs) {
+        generated = attrs::hide_impls(generated)?;
     }
-    .into()
+
+    Ok(quote! {
+        #original_struct
+        #generated
+    })
 }
 
-fn wrap_impl(mut impl_item: ItemImpl) -> TokenStream {
+fn wrap_impl(args: &Args, mut impl_item: ItemImpl) -> syn::Result<proc_macro2::TokenStream> {
+    args.ensure_known(impls::FLAGS)?;
+
+    let submodule = args
+        .value::<syn::Ident>("submodule")?
+        .map(|module| impls::submodule(&impl_item, &module))
+        .transpose()?;
+
+    if args.flag("paired") {
+        let paired = impls::paired(impl_item)?;
+        return Ok(quote! { #paired #submodule });
+    }
+
     // Check if this is a trait implementation (impl Trait for Type)
     if impl_item.trait_.is_some() {
         impl_item.unsafety = Some(syn::token::Unsafe::default());
 
         for item in &mut impl_item.items {
             if let syn::ImplItem::Fn(method) = item {
+                // A `#[noble]` trait declares every method `unsafe fn`, so the impl has to match
+                method.sig.unsafety = Some(syn::token::Unsafe::default());
+
                 let original_block = &method.block;
                 method.block = syn::parse_quote! {
                     {
@@ -123,10 +282,15 @@ fn wrap_impl(mut impl_item: ItemImpl) -> TokenStream {
         }
     }
 
-    quote! { #impl_item }.into()
+    Ok(quote! {
+        #impl_item
+        #submodule
+    })
 }
 
-fn wrap_enum(enum_item: ItemEnum) -> TokenStream {
+fn wrap_enum(args: &Args, enum_item: ItemEnum) -> syn::Result<proc_macro2::TokenStream> {
+    args.ensure_known(enums::FLAGS)?;
+
     let name = &enum_item.ident;
     let vis = &enum_item.vis;
     let attrs = &enum_item.attrs;
@@ -185,17 +349,28 @@ fn wrap_enum(enum_item: ItemEnum) -> TokenStream {
         })
         .collect();
 
-    quote! {
-        #original_enum
+    let helpers = enums::helpers(args, &enum_item)?;
 
+    let mut generated = quote! {
         impl #impl_generics #name #ty_generics #where_clause {
             #(#variant_constructors)*
         }
+
+        #helpers
+    };
+    if args.flag("hide_generated") || attrs::doc_hidden(&enum_item.attrs) {
+        generated = attrs::hide_impls(generated)?;
     }
-    .into()
+
+    Ok(quote! {
+        #original_enum
+        #generated
+    })
 }
 
-fn wrap_trait(mut trait_item: ItemTrait) -> TokenStream {
+fn wrap_trait(args: &Args, mut trait_item: ItemTrait) -> syn::Result<proc_macro2::TokenStream> {
+    args.ensure_known(traits::FLAGS)?;
+
     for item in &mut trait_item.items {
         if let syn::TraitItem::Fn(method) = item {
             method.sig.unsafety = Some(syn::token::Unsafe::default());
@@ -213,5 +388,95 @@ fn wrap_trait(mut trait_item: ItemTrait) -> TokenStream {
 
     trait_item.unsafety = Some(syn::token::Unsafe::default());
 
-    quote! { #trait_item }.into()
+    let helpers = traits::helpers(args, &trait_item)?;
+
+    Ok(quote! {
+        #trait_item
+        #helpers
+    })
+}
+
+fn wrap_static(args: &Args, static_item: ItemStatic) -> syn::Result<proc_macro2::TokenStream> {
+    args.ensure_known(statics::FLAGS)?;
+
+    if args.flag("lazy") {
+        return statics::lazy(static_item);
+    }
+
+    if args.flag("mutable_static") {
+        return statics::mutable_static(static_item);
+    }
+
+    Ok(quote! { #static_item })
+}
+
+fn wrap_mod(args: &Args, mod_item: ItemMod) -> syn::Result<proc_macro2::TokenStream> {
+    args.ensure_known(&["panic_to_errno"])?;
+
+    // `mod foo;` has nothing inline to rewrite
+    let Some((_, items)) = &mod_item.content else {
+        return Ok(quote! { #mod_item });
+    };
+
+    // `panic_to_errno` gives every function in the module one shared store and `last_error()`
+    let errno_store = args
+        .flag("panic_to_errno")
+        .then(|| format_ident!("_NOBLE_LAST_ERROR"));
+    let shared_last_error = errno_store
+        .as_ref()
+        .map(|storage| fns::shared_errno_store(&mod_item, storage))
+        .transpose()?;
+
+    let (inner_attrs, outer_attrs): (Vec<_>, Vec<_>) = mod_item
+        .attrs
+        .iter()
+        .partition(|attr| matches!(attr.style, AttrStyle::Inner(_)));
+
+    // Items with their own `#[noble(...)]` are left for that attribute to expand
+    let no_flags = Args::default();
+    let errno_flags: Args = syn::parse_quote! { panic_to_errno };
+    let wrapped = items
+        .iter()
+        .map(|item| match (item, item_attrs(item)) {
+            (_, Some(attrs)) if attrs.iter().any(is_noble) => Ok(quote! { #item }),
+            (Item::Fn(func), _) if errno_store.is_some() => {
+                wrap_function(&errno_flags, func.clone(), errno_store.as_ref())
+            }
+            _ => wrap_item(&no_flags, item.clone()),
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let vis = &mod_item.vis;
+    let unsafety = &mod_item.unsafety;
+    let mod_token = &mod_item.mod_token;
+    let ident = &mod_item.ident;
+
+    Ok(quote! {
+        #(#outer_attrs)*
+        #vis #unsafety #mod_token #ident {
+            #(#inner_attrs)*
+            #shared_last_error
+            #(#wrapped)*
+        }
+    })
+}
+
+fn item_attrs(item: &Item) -> Option<&[syn::Attribute]> {
+    match item {
+        Item::Fn(item) => Some(&item.attrs),
+        Item::Struct(item) => Some(&item.attrs),
+        Item::Impl(item) => Some(&item.attrs),
+        Item::Enum(item) => Some(&item.attrs),
+        Item::Trait(item) => Some(&item.attrs),
+        Item::Static(item) => Some(&item.attrs),
+        Item::Mod(item) => Some(&item.attrs),
+        _ => None,
+    }
+}
+
+fn is_noble(attr: &syn::Attribute) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "noble")
 }
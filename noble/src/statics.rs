@@ -0,0 +1,87 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ItemStatic;
+
+pub(crate) const FLAGS: &[&str] = &["lazy", "mutable_static"];
+
+// `static NAME: T = init;` becomes a unit value whose `get` runs `init` on first access
+pub(crate) fn lazy(item: ItemStatic) -> syn::Result<TokenStream> {
+    if let syn::StaticMutability::Mut(token) = &item.mutability {
+        return Err(syn::Error::new_spanned(
+            token,
+            "#[noble(lazy)] manages its own `static mut`, drop the `mut` here",
+        ));
+    }
+
+    let ItemStatic {
+        attrs,
+        vis,
+        ident,
+        ty,
+        expr,
+        ..
+    } = item;
+    let handle = format_ident!("__NobleLazy{}", ident);
+
+    Ok(quote! {
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        #vis struct #handle;
+
+        impl #handle {
+            /// Initializes the value on first access and hands out a `'static` reference.
+            ///
+            /// There is no synchronization whatsoever: two threads reaching the first call at
+            /// the same time race on the initialization, which is a data race and therefore UB.
+            pub unsafe fn get(&self) -> &'static #ty {
+                static mut SLOT: ::core::option::Option<#ty> = ::core::option::Option::None;
+
+                unsafe { (*&raw mut SLOT).get_or_insert_with(|| #expr) }
+            }
+        }
+
+        #(#attrs)*
+        #vis static #ident: #handle = #handle;
+    })
+}
+
+// `static NAME: T = init;` keeps its name but hands out `&mut T` through `name_mut()`,
+// without ever taking a reference to a `static mut`
+pub(crate) fn mutable_static(item: ItemStatic) -> syn::Result<TokenStream> {
+    if let syn::StaticMutability::Mut(token) = &item.mutability {
+        return Err(syn::Error::new_spanned(
+            token,
+            "#[noble(mutable_static)] replaces `static mut`, drop the `mut` here",
+        ));
+    }
+
+    let ItemStatic {
+        attrs,
+        vis,
+        ident,
+        ty,
+        expr,
+        ..
+    } = item;
+    let cell = format_ident!("__NobleMutable{}", ident);
+    let accessor = format_ident!("{}_mut", ident.to_string().to_lowercase());
+
+    Ok(quote! {
+        #[doc(hidden)]
+        #[allow(non_camel_case_types)]
+        #[repr(transparent)]
+        #vis struct #cell(::core::cell::UnsafeCell<#ty>);
+
+        unsafe impl ::core::marker::Sync for #cell {}
+
+        #(#attrs)*
+        #vis static #ident: #cell = #cell(::core::cell::UnsafeCell::new(#expr));
+
+        /// Exclusive access to the static. Nothing stops two of these from being alive at
+        /// once, or from being used on two threads; either is UB.
+        #[allow(clippy::mut_from_ref)]
+        #vis unsafe fn #accessor() -> &'static mut #ty {
+            unsafe { &mut *#ident.0.get() }
+        }
+    })
+}
@@ -0,0 +1,2000 @@
+use proc_macro2::TokenStream;
+use quote::{ToTokens, format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{
+    Field, Fields, GenericParam, Ident, ItemStruct, Lifetime, LitInt, Member, Path, ReturnType,
+    Type, TypeBareFn,
+};
+
+use crate::args::Args;
+use crate::{attrs, ty};
+
+pub(crate) const FLAGS: &[&str] = &[
+    "weak_unchecked",
+    "raw_get",
+    "field_offset",
+    "packed_read",
+    "raw_waker",
+    "atomic_view",
+    "default_zeroed",
+    "bytemuck_unsafe",
+    "mutate_through_shared",
+    "ffi_callback",
+    "unchecked_index",
+    "deref_ptr",
+    "clone_bitwise",
+    "box_leak",
+    "cast_ref",
+    "init_volatile",
+    "try_transmute",
+    "assume_aligned",
+    "utf8_unchecked",
+    "dispatch",
+    "uninit",
+    "raw_fd",
+    "hide_generated",
+    "atomic_ptr",
+    "extend_lifetime",
+    "validate_layout",
+    "as_bytes",
+    "linked_iter",
+    "split_mut",
+    "reckless",
+    "index_by_name",
+    "as_vec",
+    "deref_raw",
+    "addr_roundtrip",
+    "io",
+    "transmute_copy",
+    "swap_with",
+    "borrow_bytes",
+    "dump_layout",
+    "byte_array",
+    "no_drop",
+    "ord_bytes",
+    "as_ref_raw",
+    "send_sync_closure",
+    "union_cast",
+    "inline_buffer",
+];
+
+// What `#[noble(reckless)]` turns on, for when one dangerous convenience isn't enough
+const RECKLESS: &[&str] = &[
+    "default_zeroed",
+    "as_bytes",
+    "mutate_through_shared",
+    "extend_lifetime",
+];
+
+// Extra unsafe helpers requested through `#[noble(...)]` flags on a struct
+pub(crate) fn helpers(args: &Args, item: &ItemStruct) -> syn::Result<TokenStream> {
+    let mut methods = Vec::new();
+    let mut impls = Vec::new();
+
+    let enabled =
+        |flag: &str| args.flag(flag) || (args.flag("reckless") && RECKLESS.contains(&flag));
+
+    if args.flag("weak_unchecked") {
+        methods.push(weak_unchecked(item)?);
+    }
+
+    if args.flag("raw_get") {
+        methods.push(raw_get(item)?);
+    }
+
+    if args.flag("field_offset") {
+        methods.push(field_offset(item));
+    }
+
+    if args.flag("packed_read") {
+        methods.push(packed_read(item)?);
+    }
+
+    if args.flag("raw_waker") {
+        methods.push(raw_waker(item)?);
+    }
+
+    if args.flag("atomic_view") {
+        methods.push(atomic_view(item)?);
+    }
+
+    if args.flag("atomic_ptr") {
+        methods.push(atomic_ptr(item)?);
+    }
+
+    if args.flag("dump_layout") {
+        methods.push(dump_layout(item));
+    }
+
+    if args.flag("union_cast") {
+        methods.push(union_cast(item)?);
+    }
+
+    if let Some(options) = args.nested("byte_array")? {
+        methods.push(byte_array(item, &options)?);
+    }
+
+    if args.flag("addr_roundtrip") {
+        methods.push(addr_roundtrip(item));
+    }
+
+    if enabled("mutate_through_shared") {
+        methods.push(mutate_through_shared());
+    }
+
+    if let Some(signature) = args.value::<TypeBareFn>("ffi_callback")? {
+        methods.push(ffi_callback(item, &signature)?);
+    }
+
+    if args.flag("deref_ptr") {
+        methods.push(deref_ptr(item)?);
+    }
+
+    if args.flag("box_leak") {
+        methods.push(box_leak(item)?);
+    }
+
+    if args.flag("index_by_name") {
+        methods.push(index_by_name(item));
+    }
+
+    if args.flag("split_mut") {
+        methods.push(split_mut(item)?);
+    }
+
+    if args.flag("as_vec") {
+        methods.push(as_vec(item)?);
+    }
+
+    if enabled("as_bytes") {
+        methods.push(as_bytes());
+    }
+
+    if enabled("extend_lifetime") {
+        methods.push(extend_lifetime());
+    }
+
+    if let Some(target) = args.value::<Type>("cast_ref")? {
+        methods.push(cast_ref(&target));
+    }
+
+    if let Some(target) = args.value::<Type>("transmute_copy")? {
+        methods.push(transmute_copy(&target));
+    }
+
+    if let Some(other) = args.value::<Type>("swap_with")? {
+        methods.push(swap_with(&other));
+    }
+
+    if args.flag("init_volatile") {
+        methods.push(init_volatile(item));
+    }
+
+    if args.flag("utf8_unchecked") {
+        methods.push(utf8_unchecked(item)?);
+    }
+
+    if let Some(dispatch_args) = args.nested("dispatch")? {
+        methods.push(dispatch(item, &dispatch_args)?);
+    }
+
+    if let Some(align) = args.value::<LitInt>("assume_aligned")? {
+        methods.push(assume_aligned(item, &align)?);
+    }
+
+    if enabled("default_zeroed") {
+        impls.push(default_zeroed(item)?);
+    }
+
+    if let Some(paths) = args.nested("bytemuck_unsafe")? {
+        impls.push(bytemuck_unsafe(item, &paths)?);
+    }
+
+    if args.flag("unchecked_index") {
+        impls.push(unchecked_index(item)?);
+    }
+
+    if args.flag("clone_bitwise") {
+        impls.push(clone_bitwise(item)?);
+    }
+
+    if args.flag("raw_fd") {
+        impls.push(raw_fd(item)?);
+    }
+
+    if let Some(source) = args.value::<Type>("try_transmute")? {
+        impls.push(try_transmute(item, &source));
+    }
+
+    if args.flag("deref_raw") {
+        impls.push(deref_raw(item)?);
+    }
+
+    if args.flag("as_ref_raw") {
+        impls.push(as_ref_raw(item)?);
+    }
+
+    if args.flag("send_sync_closure") {
+        impls.push(send_sync_closure(item)?);
+    }
+
+    if let Some(options) = args.nested("inline_buffer")? {
+        impls.push(inline_buffer(item, &options)?);
+    }
+
+    if args.flag("linked_iter") {
+        impls.push(linked_iter(item)?);
+    }
+
+    if args.flag("io") {
+        impls.push(io(item)?);
+    }
+
+    if args.flag("borrow_bytes") {
+        impls.push(borrow_bytes(item));
+    }
+
+    if args.flag("ord_bytes") {
+        impls.push(ord_bytes(item)?);
+    }
+
+    if let Some(expected) = args.nested("validate_layout")? {
+        impls.push(validate_layout(item, &expected)?);
+    }
+
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+    let inherent = (!methods.is_empty()).then(|| {
+        quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                #(#methods)*
+            }
+        }
+    });
+
+    Ok(quote! {
+        #inherent
+        #(#impls)*
+    })
+}
+
+// How to reach a field through `self`, and the stem used to name its accessors
+fn field_names(index: usize, field: &Field) -> (Member, Ident) {
+    match &field.ident {
+        Some(ident) => (Member::Named(ident.clone()), ident.clone()),
+        None => (Member::from(index), format_ident!("field_{}", index)),
+    }
+}
+
+fn weak_unchecked(item: &ItemStruct) -> syn::Result<TokenStream> {
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(weak_unchecked)] needs noble's `std` feature",
+        ));
+    }
+
+    let mut methods = Vec::new();
+
+    for (index, field) in item.fields.iter().enumerate() {
+        let Some(pointee) = ty::type_args(&field.ty, "Weak").and_then(|args| args.first().copied())
+        else {
+            continue;
+        };
+
+        let strong = strong_pointer(&field.ty)?;
+        let (member, stem) = field_names(index, field);
+        let method = format_ident!("{}_upgrade_unchecked", stem);
+
+        methods.push(quote! {
+            /// Upgrades without checking that the value is still alive.
+            pub unsafe fn #method(&self) -> #strong<#pointee> {
+                unsafe { self.#member.upgrade().unwrap_unchecked() }
+            }
+        });
+    }
+
+    if methods.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(weak_unchecked)] needs at least one `Weak<T>` field",
+        ));
+    }
+
+    Ok(quote! { #(#methods)* })
+}
+
+// `rc::Weak` upgrades to `Rc`, `sync::Weak` to `Arc`; a bare `Weak` could be either
+fn strong_pointer(ty: &Type) -> syn::Result<TokenStream> {
+    let module = ty::path_of(ty)
+        .and_then(|path| path.segments.iter().rev().nth(1))
+        .map(|segment| segment.ident.to_string());
+
+    match module.as_deref() {
+        Some("rc") => Ok(quote! { ::std::rc::Rc }),
+        Some("sync") => Ok(quote! { ::std::sync::Arc }),
+        _ => Err(syn::Error::new_spanned(
+            ty,
+            "write `rc::Weak<T>` or `sync::Weak<T>` so #[noble(weak_unchecked)] knows whether to return `Rc` or `Arc`",
+        )),
+    }
+}
+
+fn raw_get(item: &ItemStruct) -> syn::Result<TokenStream> {
+    let mut maps =
+        item.fields.iter().enumerate().filter_map(|(index, field)| {
+            match ty::type_args(&field.ty, "HashMap")?.as_slice() {
+                [key, value, ..] => Some((index, field, *key, *value)),
+                _ => None,
+            }
+        });
+
+    let Some((index, field, key, value)) = maps.next() else {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(raw_get)] needs a `HashMap<K, V>` field",
+        ));
+    };
+
+    if let Some((_, extra, ..)) = maps.next() {
+        return Err(syn::Error::new_spanned(
+            extra,
+            "#[noble(raw_get)] supports exactly one `HashMap<K, V>` field",
+        ));
+    }
+
+    let (member, _) = field_names(index, field);
+
+    Ok(quote! {
+        /// Looks up `key` without checking that it is present.
+        pub unsafe fn get_unchecked(&self, key: &#key) -> &#value {
+            unsafe { self.#member.get(key).unwrap_unchecked() }
+        }
+    })
+}
+
+fn field_offset(item: &ItemStruct) -> TokenStream {
+    let offsets = item.fields.iter().enumerate().map(|(index, field)| {
+        let (member, stem) = field_names(index, field);
+        let constant = format_ident!("{}_OFFSET", stem.unraw().to_string().to_uppercase());
+
+        quote! {
+            pub const #constant: usize = ::core::mem::offset_of!(Self, #member);
+        }
+    });
+
+    quote! { #(#offsets)* }
+}
+
+// Offsets only exist once the type is laid out, after expansion, so the macro prints the
+// fields it can see and leaves the numbers to a `const` the compiler evaluates
+fn dump_layout(item: &ItemStruct) -> TokenStream {
+    let mut dump = format!("#[noble(dump_layout)] {}", item.ident);
+    for (index, field) in item.fields.iter().enumerate() {
+        let (_, stem) = field_names(index, field);
+        dump.push_str(&format!(
+            "\n    {}: {}",
+            stem.unraw(),
+            field.ty.to_token_stream()
+        ));
+    }
+    dump.push_str(&format!(
+        "\n    offsets in `{0}::LAYOUT`, size and alignment in `{0}::SIZE` and `{0}::ALIGN`",
+        item.ident
+    ));
+    print_note(&item.ident, &dump);
+
+    let entries = item.fields.iter().enumerate().map(|(index, field)| {
+        let (member, stem) = field_names(index, field);
+        let stem = stem.unraw().to_string();
+
+        quote! { (#stem, ::core::mem::offset_of!(Self, #member)) }
+    });
+
+    quote! {
+        /// Every field's name and offset, in declaration order, for comparing against C headers.
+        pub const LAYOUT: &'static [(&'static str, usize)] = &[#(#entries),*];
+
+        /// The struct's total `size_of`.
+        pub const SIZE: usize = ::core::mem::size_of::<Self>();
+
+        /// The struct's `align_of`.
+        pub const ALIGN: usize = ::core::mem::align_of::<Self>();
+    }
+}
+
+// On nightly the dump is a proper `note:` diagnostic; otherwise it goes straight to stderr,
+// which cargo shows whenever the crate is rebuilt
+#[cfg(feature = "nightly")]
+fn print_note(ident: &Ident, message: &str) {
+    proc_macro::Diagnostic::spanned(ident.span().unwrap(), proc_macro::Level::Note, message).emit();
+}
+
+#[cfg(not(feature = "nightly"))]
+fn print_note(_: &Ident, message: &str) {
+    eprintln!("note: {}", message);
+}
+
+fn packed_read(item: &ItemStruct) -> syn::Result<TokenStream> {
+    if !attrs::repr_hints(&item.attrs)?
+        .iter()
+        .any(|hint| hint == "packed")
+    {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(packed_read)] is for #[repr(packed)] structs",
+        ));
+    }
+
+    let reads = item.fields.iter().enumerate().map(|(index, field)| {
+        let (member, stem) = field_names(index, field);
+        let method = format_ident!("read_{}", stem.unraw());
+        let ty = &field.ty;
+
+        // Packed fields may be unaligned, so they are copied out and never borrowed
+        quote! {
+            pub unsafe fn #method(&self) -> #ty
+            where
+                #ty: ::core::marker::Copy,
+            {
+                unsafe { ::core::ptr::read_unaligned(::core::ptr::addr_of!(self.#member)) }
+            }
+        }
+    });
+
+    Ok(quote! { #(#reads)* })
+}
+
+// The data pointer is an `Arc<Self>`; waking delegates to the struct's own `wake(&self)`
+fn raw_waker(item: &ItemStruct) -> syn::Result<TokenStream> {
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(raw_waker)] needs noble's `std` feature",
+        ));
+    }
+
+    Ok(quote! {
+        pub const RAW_WAKER_VTABLE: ::core::task::RawWakerVTable = ::core::task::RawWakerVTable::new(
+            Self::__noble_waker_clone,
+            Self::__noble_waker_wake,
+            Self::__noble_waker_wake_by_ref,
+            Self::__noble_waker_drop,
+        );
+
+        /// Wraps this value into a `Waker` backed by `RAW_WAKER_VTABLE`.
+        ///
+        /// `Waker` is `Send + Sync`, which nobody checked this type is.
+        pub unsafe fn into_waker(self: ::std::sync::Arc<Self>) -> ::core::task::Waker {
+            let data = ::std::sync::Arc::into_raw(self).cast::<()>();
+
+            unsafe { ::core::task::Waker::from_raw(::core::task::RawWaker::new(data, &Self::RAW_WAKER_VTABLE)) }
+        }
+
+        #[doc(hidden)]
+        unsafe fn __noble_waker_clone(data: *const ()) -> ::core::task::RawWaker {
+            unsafe { ::std::sync::Arc::increment_strong_count(data.cast::<Self>()) };
+
+            ::core::task::RawWaker::new(data, &Self::RAW_WAKER_VTABLE)
+        }
+
+        #[doc(hidden)]
+        unsafe fn __noble_waker_wake(data: *const ()) {
+            let this = unsafe { ::std::sync::Arc::from_raw(data.cast::<Self>()) };
+            Self::wake(&this);
+        }
+
+        #[doc(hidden)]
+        unsafe fn __noble_waker_wake_by_ref(data: *const ()) {
+            Self::wake(unsafe { &*data.cast::<Self>() });
+        }
+
+        #[doc(hidden)]
+        unsafe fn __noble_waker_drop(data: *const ()) {
+            drop(unsafe { ::std::sync::Arc::from_raw(data.cast::<Self>()) });
+        }
+    })
+}
+
+fn atomic_view(item: &ItemStruct) -> syn::Result<TokenStream> {
+    let mut views = Vec::new();
+
+    for (index, field) in item.fields.iter().enumerate() {
+        let Some(atomic) = ty::atomic_twin(&field.ty) else {
+            continue;
+        };
+
+        let (member, stem) = field_names(index, field);
+        let method = format_ident!("{}_atomic", stem.unraw());
+
+        views.push(quote! {
+            /// Reinterprets the field as its atomic twin, mutating through a shared borrow.
+            pub unsafe fn #method(&self) -> &::core::sync::atomic::#atomic {
+                unsafe {
+                    ::core::sync::atomic::#atomic::from_ptr(
+                        ::core::ptr::addr_of!(self.#member).cast_mut(),
+                    )
+                }
+            }
+        });
+    }
+
+    if views.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(atomic_view)] needs at least one integer field",
+        ));
+    }
+
+    Ok(quote! { #(#views)* })
+}
+
+fn default_zeroed(item: &ItemStruct) -> syn::Result<TokenStream> {
+    if let Some(derive) = attrs::derives(&item.attrs, "Default")? {
+        return Err(syn::Error::new_spanned(
+            derive,
+            "#[noble(default_zeroed)] generates `Default`, drop the derive",
+        ));
+    }
+
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::core::default::Default for #name #ty_generics #where_clause {
+            /// All zero bytes, whether or not that is a valid value of every field.
+            fn default() -> Self {
+                unsafe { ::core::mem::zeroed() }
+            }
+        }
+    })
+}
+
+// `Pod` and `Zeroable` resolve at the call site unless given as `pod = "..."`/`zeroable = "..."`
+fn bytemuck_unsafe(item: &ItemStruct, paths: &Args) -> syn::Result<TokenStream> {
+    paths.ensure_known(&["pod", "zeroable"])?;
+
+    let pod = paths
+        .value::<Path>("pod")?
+        .unwrap_or_else(|| syn::parse_quote! { Pod });
+    let zeroable = paths
+        .value::<Path>("zeroable")?
+        .unwrap_or_else(|| syn::parse_quote! { Zeroable });
+
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    Ok(quote! {
+        unsafe impl #impl_generics #pod for #name #ty_generics #where_clause {}
+        unsafe impl #impl_generics #zeroable for #name #ty_generics #where_clause {}
+    })
+}
+
+fn mutate_through_shared() -> TokenStream {
+    quote! {
+        /// Turns a shared borrow into an exclusive one.
+        ///
+        /// Any use of the result while another reference to `self` exists is aliasing UB, and
+        /// since you started from `&self`, one does.
+        #[allow(invalid_reference_casting, clippy::mut_from_ref)]
+        pub unsafe fn get_mut_unchecked(&self) -> &mut Self {
+            unsafe { &mut *(self as *const Self as *mut Self) }
+        }
+    }
+}
+
+// The closure lives in the only field, or in one called `callback`
+fn ffi_callback(item: &ItemStruct, signature: &TypeBareFn) -> syn::Result<TokenStream> {
+    let closure = if item.fields.len() == 1 {
+        item.fields.iter().next().map(|field| (0, field))
+    } else {
+        item.fields.iter().enumerate().find(|(_, field)| {
+            field
+                .ident
+                .as_ref()
+                .is_some_and(|ident| ident == "callback")
+        })
+    };
+
+    let Some((index, field)) = closure else {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(ffi_callback)] needs a single field or one named `callback` holding the closure",
+        ));
+    };
+
+    let (member, _) = field_names(index, field);
+    let closure_ty = &field.ty;
+    let arg_types: Vec<_> = signature.inputs.iter().map(|arg| &arg.ty).collect();
+    let arg_names: Vec<_> = (0..arg_types.len())
+        .map(|index| format_ident!("arg{}", index))
+        .collect();
+    let output = match &signature.output {
+        ReturnType::Default => quote! {},
+        ReturnType::Type(arrow, ty) => quote! { #arrow #ty },
+    };
+
+    Ok(quote! {
+        /// C-callable entry point; `userdata` must come from [`Self::as_c_callback`] on a live value.
+        pub unsafe extern "C" fn trampoline(
+            userdata: *mut ::core::ffi::c_void,
+            #(#arg_names: #arg_types),*
+        ) #output
+        where
+            #closure_ty: ::core::ops::FnMut(#(#arg_types),*) #output,
+        {
+            let this = unsafe { &mut *userdata.cast::<Self>() };
+            (this.#member)(#(#arg_names),*)
+        }
+
+        /// The trampoline and the `void*` to hand to C together.
+        pub fn as_c_callback(
+            &mut self,
+        ) -> (
+            unsafe extern "C" fn(*mut ::core::ffi::c_void #(, #arg_types)*) #output,
+            *mut ::core::ffi::c_void,
+        )
+        where
+            #closure_ty: ::core::ops::FnMut(#(#arg_types),*) #output,
+        {
+            (Self::trampoline, (self as *mut Self).cast())
+        }
+    })
+}
+
+fn unchecked_index(item: &ItemStruct) -> syn::Result<TokenStream> {
+    let mut sequences = item
+        .fields
+        .iter()
+        .enumerate()
+        .filter_map(|(index, field)| Some((index, field, ty::element_type(&field.ty)?)));
+
+    let Some((index, field, elem)) = sequences.next() else {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(unchecked_index)] needs a `Vec<T>`, `Box<[T]>`, `[T; N]` or `[T]` field",
+        ));
+    };
+
+    if let Some((_, extra, _)) = sequences.next() {
+        return Err(syn::Error::new_spanned(
+            extra,
+            "#[noble(unchecked_index)] supports exactly one sequence field",
+        ));
+    }
+
+    let (member, _) = field_names(index, field);
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    // Out of bounds indices are UB rather than a panic
+    Ok(quote! {
+        impl #impl_generics ::core::ops::Index<usize> for #name #ty_generics #where_clause {
+            type Output = #elem;
+
+            fn index(&self, index: usize) -> &Self::Output {
+                unsafe { self.#member.get_unchecked(index) }
+            }
+        }
+
+        impl #impl_generics ::core::ops::IndexMut<usize> for #name #ty_generics #where_clause {
+            fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+                unsafe { self.#member.get_unchecked_mut(index) }
+            }
+        }
+    })
+}
+
+// The one raw pointer field of `item`, for the helpers that dereference it
+fn pointer_field<'a>(
+    item: &'a ItemStruct,
+    flag: &str,
+) -> syn::Result<(Member, &'a Field, &'a Type)> {
+    let mut pointers = item
+        .fields
+        .iter()
+        .enumerate()
+        .filter_map(|(index, field)| Some((index, field, ty::pointee(&field.ty)?)));
+
+    let Some((index, field, pointee)) = pointers.next() else {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            format!("#[noble({})] needs a `*const T` or `*mut T` field", flag),
+        ));
+    };
+
+    if let Some((_, extra, _)) = pointers.next() {
+        return Err(syn::Error::new_spanned(
+            extra,
+            format!("#[noble({})] supports exactly one raw pointer field", flag),
+        ));
+    }
+
+    Ok((field_names(index, field).0, field, pointee))
+}
+
+fn deref_ptr(item: &ItemStruct) -> syn::Result<TokenStream> {
+    let (member, _, pointee) = pointer_field(item, "deref_ptr")?;
+
+    Ok(quote! {
+        /// Dereferences the stored pointer, trusting it is non-null, aligned and alive.
+        pub unsafe fn as_ref_unchecked(&self) -> &#pointee {
+            unsafe { &*self.#member }
+        }
+    })
+}
+
+fn clone_bitwise(item: &ItemStruct) -> syn::Result<TokenStream> {
+    if let Some(derive) = attrs::derives(&item.attrs, "Clone")? {
+        return Err(syn::Error::new_spanned(
+            derive,
+            "#[noble(clone_bitwise)] generates `Clone`, drop the derive",
+        ));
+    }
+
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::core::clone::Clone for #name #ty_generics #where_clause {
+            /// A bitwise copy: anything owning a resource now has two owners to drop it.
+            fn clone(&self) -> Self {
+                unsafe { ::core::ptr::read(self) }
+            }
+        }
+    })
+}
+
+fn box_leak(item: &ItemStruct) -> syn::Result<TokenStream> {
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(box_leak)] needs noble's `std` feature",
+        ));
+    }
+
+    Ok(quote! {
+        /// Moves the value to the heap and leaks it.
+        pub fn leak_static(self) -> &'static mut Self
+        where
+            Self: 'static,
+        {
+            ::std::boxed::Box::leak(::std::boxed::Box::new(self))
+        }
+
+        /// Takes back a value leaked by [`Self::leak_static`]; any other reference is UB.
+        pub unsafe fn from_static_mut(leaked: &'static mut Self) -> ::std::boxed::Box<Self>
+        where
+            Self: 'static,
+        {
+            unsafe { ::std::boxed::Box::from_raw(leaked) }
+        }
+    })
+}
+
+fn cast_ref(target: &Type) -> TokenStream {
+    quote! {
+        /// Reinterprets the borrow as the other type. Sizes must match and the target can't
+        /// need stricter alignment, both checked at compile time; the rest is on you.
+        pub unsafe fn as_other(&self) -> &#target {
+            const {
+                assert!(
+                    ::core::mem::size_of::<Self>() == ::core::mem::size_of::<#target>(),
+                    "#[noble(cast_ref)] needs both types to have the same size",
+                );
+                assert!(
+                    ::core::mem::align_of::<#target>() <= ::core::mem::align_of::<Self>(),
+                    "#[noble(cast_ref)] target type needs stricter alignment",
+                );
+            }
+
+            unsafe { &*(self as *const Self as *const #target) }
+        }
+    }
+}
+
+fn transmute_copy(target: &Type) -> TokenStream {
+    quote! {
+        /// Reads the other type out of this value's leading bytes, leaving `self` in place.
+        /// Nothing checks alignment or validity, only `transmute_copy`'s own panic when the
+        /// target is larger.
+        pub unsafe fn transmute_copy_to(&self) -> #target {
+            unsafe { ::core::mem::transmute_copy::<Self, #target>(self) }
+        }
+    }
+}
+
+fn swap_with(other: &Type) -> TokenStream {
+    quote! {
+        /// Exchanges the bytes of the two values. Equal sizes are checked at compile time,
+        /// that each side's bytes make sense as the other type is not.
+        pub unsafe fn swap_with(&mut self, other: &mut #other) {
+            const {
+                assert!(
+                    ::core::mem::size_of::<Self>() == ::core::mem::size_of::<#other>(),
+                    "#[noble(swap_with)] needs both types to have the same size",
+                );
+            }
+
+            unsafe {
+                ::core::ptr::swap_nonoverlapping(
+                    (self as *mut Self).cast::<u8>(),
+                    (other as *mut #other).cast::<u8>(),
+                    ::core::mem::size_of::<Self>(),
+                );
+            }
+        }
+    }
+}
+
+// Every field is written exactly once with a volatile store, in declaration order
+fn init_volatile(item: &ItemStruct) -> TokenStream {
+    let (members, params): (Vec<_>, Vec<_>) = item
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| field_names(index, field))
+        .unzip();
+    let types: Vec<_> = item.fields.iter().map(|field| &field.ty).collect();
+
+    quote! {
+        /// Writes every field with `write_volatile`, as a register block expects.
+        pub unsafe fn init(&mut self, #(#params: #types),*)
+        where
+            #(#types: ::core::marker::Copy,)*
+        {
+            unsafe {
+                #(::core::ptr::write_volatile(::core::ptr::addr_of_mut!(self.#members), #params);)*
+            }
+        }
+    }
+}
+
+// The sizes are compared at runtime, so a mismatch hands the value back instead of failing to build
+fn try_transmute(item: &ItemStruct, source: &Type) -> TokenStream {
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::core::convert::TryFrom<#source> for #name #ty_generics #where_clause {
+            type Error = #source;
+
+            /// Reinterprets the bytes of `value` when the sizes match, and returns it untouched otherwise.
+            fn try_from(value: #source) -> ::core::result::Result<Self, Self::Error> {
+                if ::core::mem::size_of::<#source>() != ::core::mem::size_of::<Self>() {
+                    return ::core::result::Result::Err(value);
+                }
+
+                let value = ::core::mem::ManuallyDrop::new(value);
+                ::core::result::Result::Ok(unsafe { ::core::mem::transmute_copy(&*value) })
+            }
+        }
+    }
+}
+
+fn assume_aligned(item: &ItemStruct, align: &LitInt) -> syn::Result<TokenStream> {
+    if !align.base10_parse::<usize>()?.is_power_of_two() {
+        return Err(syn::Error::new_spanned(
+            align,
+            "#[noble(assume_aligned)] needs a power of two",
+        ));
+    }
+
+    let (member, _, _) = pointer_field(item, "assume_aligned")?;
+
+    Ok(quote! {
+        /// Casts the stored pointer, promising it is aligned to the declared boundary; only
+        /// debug builds check.
+        pub unsafe fn aligned_ptr<U>(&self) -> *const U {
+            let ptr = self.#member as *const U;
+            debug_assert!(
+                ptr.addr() % #align == 0,
+                "#[noble(assume_aligned)] pointer is misaligned",
+            );
+            ptr
+        }
+    })
+}
+
+fn utf8_unchecked(item: &ItemStruct) -> syn::Result<TokenStream> {
+    let mut byte_fields = item
+        .fields
+        .iter()
+        .enumerate()
+        .filter(|(_, field)| ty::is_bytes(&field.ty));
+
+    let Some((index, field)) = byte_fields.next() else {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(utf8_unchecked)] needs a `Vec<u8>` or `&[u8]` field",
+        ));
+    };
+
+    if let Some((_, extra)) = byte_fields.next() {
+        return Err(syn::Error::new_spanned(
+            extra,
+            "#[noble(utf8_unchecked)] supports exactly one byte field",
+        ));
+    }
+
+    let (member, _) = field_names(index, field);
+
+    Ok(quote! {
+        /// Views the bytes as text without validating that they are UTF-8.
+        pub unsafe fn as_str_unchecked(&self) -> &str {
+            unsafe { ::core::str::from_utf8_unchecked(&self.#member) }
+        }
+    })
+}
+
+// Calls go through the vtable field, passing the type-erased `data` pointer first
+fn dispatch(item: &ItemStruct, dispatch_args: &Args) -> syn::Result<TokenStream> {
+    let Some(vtable) = dispatch_args.value::<Type>("vtable")? else {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(dispatch)] needs the table type, as in `dispatch(vtable = \"MyVtable\", ...)`",
+        ));
+    };
+
+    let find = |matches: &dyn Fn(&Type) -> bool, what: &str| {
+        item.fields
+            .iter()
+            .enumerate()
+            .find(|(_, field)| ty::points_to(&field.ty).is_some_and(matches))
+            .map(|(index, field)| field_names(index, field).0)
+            .ok_or_else(|| {
+                syn::Error::new_spanned(
+                    &item.ident,
+                    format!("#[noble(dispatch)] needs a {} field", what),
+                )
+            })
+    };
+    let table = find(
+        &|ty| *ty == vtable,
+        "`&'static MyVtable` or `*const MyVtable`",
+    )?;
+    let data = find(
+        &|ty| matches!(ty, Type::Tuple(unit) if unit.elems.is_empty()),
+        "`*mut ()` or `*const ()`",
+    )?;
+
+    let methods = match dispatch_args.nested("methods")? {
+        Some(methods) => methods.pairs::<TypeBareFn>()?,
+        None => Vec::new(),
+    };
+    if methods.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(dispatch)] needs the table's methods, as in `methods(area = \"fn() -> f64\")`",
+        ));
+    }
+
+    let calls = methods.iter().map(|(name, signature)| {
+        let method = format_ident!("call_{}", name.unraw());
+        let output = &signature.output;
+        let (arg_names, arg_types): (Vec<_>, Vec<_>) = signature
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(index, input)| (format_ident!("arg{}", index), &input.ty))
+            .unzip();
+
+        quote! {
+            /// Calls through the vtable, trusting that it matches what `data` points at.
+            pub unsafe fn #method(&self #(, #arg_names: #arg_types)*) #output {
+                unsafe { ((*self.#table).#name)(self.#data #(, #arg_names)*) }
+            }
+        }
+    });
+
+    Ok(quote! { #(#calls)* })
+}
+
+// Moves every field into a `<Name>Fields` struct held in a single `ManuallyDrop`, so the
+// struct itself has no drop glue and dropping it leaks whatever the fields own
+pub(crate) fn no_drop(item: &mut ItemStruct) -> syn::Result<TokenStream> {
+    if item.fields.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(no_drop)] needs a struct with fields",
+        ));
+    }
+
+    let mut inner = item.clone();
+    inner.ident = format_ident!("{}Fields", item.ident);
+    inner
+        .attrs
+        .retain(|attr| attr.path().is_ident("repr") || attr.path().is_ident("derive"));
+    let inner_doc = format!("The fields of `{}`, which never drops them.", item.ident);
+
+    let name = &item.ident;
+    let vis = &item.vis;
+    let inner_name = &inner.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+    let wrapped: Type = syn::parse_quote! { ::core::mem::ManuallyDrop<#inner_name #ty_generics> };
+
+    let (holder, fields): (Member, _) = match &item.fields {
+        Fields::Named(_) => (
+            syn::parse_quote! { fields },
+            Fields::Named(syn::parse_quote! { { #vis fields: #wrapped } }),
+        ),
+        _ => (
+            syn::parse_quote! { 0 },
+            Fields::Unnamed(syn::parse_quote! { (#vis #wrapped) }),
+        ),
+    };
+
+    let accessors = inner.fields.iter().enumerate().map(|(index, field)| {
+        let (member, stem) = field_names(index, field);
+        let getter = format_ident!("{}", stem.unraw());
+        let getter_mut = format_ident!("{}_mut", stem.unraw());
+        let ty = &field.ty;
+
+        quote! {
+            /// Borrows the field inside the `ManuallyDrop`; it must not have been dropped yet.
+            pub unsafe fn #getter(&self) -> &#ty {
+                &self.#holder.#member
+            }
+
+            /// Mutably borrows the field inside the `ManuallyDrop`; it must not have been dropped yet.
+            pub unsafe fn #getter_mut(&mut self) -> &mut #ty {
+                &mut self.#holder.#member
+            }
+        }
+    });
+
+    let output = quote! {
+        #[doc = #inner_doc]
+        #inner
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Wraps the fields; nothing will drop them unless `drop_fields` is called.
+            pub fn from_fields(fields: #inner_name #ty_generics) -> Self {
+                Self { #holder: ::core::mem::ManuallyDrop::new(fields) }
+            }
+
+            /// Moves the fields back out, so they drop normally again.
+            pub unsafe fn into_fields(self) -> #inner_name #ty_generics {
+                ::core::mem::ManuallyDrop::into_inner(self.#holder)
+            }
+
+            /// Runs the fields' destructors in place. Touching them afterwards, or calling this
+            /// twice, is UB.
+            pub unsafe fn drop_fields(&mut self) {
+                unsafe { ::core::mem::ManuallyDrop::drop(&mut self.#holder) }
+            }
+
+            #(#accessors)*
+        }
+    };
+
+    item.fields = fields;
+
+    Ok(output)
+}
+
+// Turns every field into `MaybeUninit<T>` in place and returns the fully initialized
+// `<Name>Init` mirror, plus the setters and `assume_init` that move between the two
+pub(crate) fn uninit(item: &mut ItemStruct) -> TokenStream {
+    let mut mirror = item.clone();
+    mirror.ident = format_ident!("{}Init", item.ident);
+    mirror.attrs.retain(|attr| attr.path().is_ident("repr"));
+    let mirror_doc = format!("`{}` with every field initialized.", item.ident);
+
+    let mut setters = Vec::new();
+    let mut reads = Vec::new();
+
+    for (index, field) in item.fields.iter_mut().enumerate() {
+        let (member, stem) = field_names(index, field);
+        let setter = format_ident!("set_{}", stem.unraw());
+        let ty = field.ty.clone();
+
+        setters.push(quote! {
+            /// Initializes the field. Setting it twice leaks the first value.
+            pub unsafe fn #setter(&mut self, value: #ty) {
+                self.#member.write(value);
+            }
+        });
+        reads.push(quote! {
+            #member: unsafe { self.#member.assume_init() }
+        });
+
+        field.ty = syn::parse_quote! { ::core::mem::MaybeUninit<#ty> };
+    }
+
+    let members = item
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| field_names(index, field).0);
+
+    let name = &item.ident;
+    let mirror_name = &mirror.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    quote! {
+        #[doc = #mirror_doc]
+        #mirror
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Every field left uninitialized, to be filled in with the setters.
+            pub fn uninit() -> Self {
+                Self {
+                    #(#members: ::core::mem::MaybeUninit::uninit(),)*
+                }
+            }
+
+            #(#setters)*
+
+            /// Moves every field out, trusting that all of them were set.
+            pub unsafe fn assume_init(self) -> #mirror_name #ty_generics {
+                #mirror_name {
+                    #(#reads,)*
+                }
+            }
+        }
+    }
+}
+
+// The descriptor is the field named `fd`, or else the only `RawFd`/`i32` field;
+// any other fields start out as their defaults
+fn raw_fd(item: &ItemStruct) -> syn::Result<TokenStream> {
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(raw_fd)] needs noble's `std` feature",
+        ));
+    }
+
+    let named_fd = item
+        .fields
+        .iter()
+        .enumerate()
+        .find(|(_, field)| field.ident.as_ref().is_some_and(|ident| ident == "fd"));
+    let (fd_index, fd_field) = match named_fd {
+        Some(found) => found,
+        None => {
+            let mut descriptors = item.fields.iter().enumerate().filter(|(_, field)| {
+                ty::path_of(&field.ty)
+                    .and_then(|path| path.segments.last())
+                    .is_some_and(|segment| segment.ident == "RawFd" || segment.ident == "i32")
+            });
+
+            let Some(found) = descriptors.next() else {
+                return Err(syn::Error::new_spanned(
+                    &item.ident,
+                    "#[noble(raw_fd)] needs a field named `fd` or a `RawFd` field",
+                ));
+            };
+
+            if let Some((_, extra)) = descriptors.next() {
+                return Err(syn::Error::new_spanned(
+                    extra,
+                    "#[noble(raw_fd)] found more than one `RawFd` field, name the descriptor `fd`",
+                ));
+            }
+
+            found
+        }
+    };
+
+    let (fd_member, _) = field_names(fd_index, fd_field);
+    let inits = item.fields.iter().enumerate().map(|(index, field)| {
+        let (member, _) = field_names(index, field);
+        if index == fd_index {
+            quote! { #member: fd }
+        } else {
+            quote! { #member: ::core::default::Default::default() }
+        }
+    });
+
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    Ok(quote! {
+        #[cfg(unix)]
+        impl #impl_generics ::std::os::fd::FromRawFd for #name #ty_generics #where_clause {
+            unsafe fn from_raw_fd(fd: ::std::os::fd::RawFd) -> Self {
+                Self {
+                    #(#inits,)*
+                }
+            }
+        }
+
+        #[cfg(unix)]
+        impl #impl_generics ::std::os::fd::AsRawFd for #name #ty_generics #where_clause {
+            fn as_raw_fd(&self) -> ::std::os::fd::RawFd {
+                self.#fd_member
+            }
+        }
+    })
+}
+
+fn atomic_ptr(item: &ItemStruct) -> syn::Result<TokenStream> {
+    let mut views = Vec::new();
+
+    for (index, field) in item.fields.iter().enumerate() {
+        let Some(pointee) = ty::mut_pointee(&field.ty) else {
+            continue;
+        };
+
+        let (member, stem) = field_names(index, field);
+        let method = format_ident!("{}_atomic", stem.unraw());
+
+        views.push(quote! {
+            /// Reinterprets the pointer field as an `AtomicPtr`, mutating through a shared borrow.
+            pub unsafe fn #method(&self) -> &::core::sync::atomic::AtomicPtr<#pointee> {
+                unsafe {
+                    ::core::sync::atomic::AtomicPtr::from_ptr(
+                        ::core::ptr::addr_of!(self.#member).cast_mut(),
+                    )
+                }
+            }
+        });
+    }
+
+    if views.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(atomic_ptr)] needs at least one `*mut T` field",
+        ));
+    }
+
+    Ok(quote! { #(#views)* })
+}
+
+// Exposes each field's provenance so the address can travel as a plain `usize` and be turned
+// back into a pointer later, out of sight of the aliasing model
+fn addr_roundtrip(item: &ItemStruct) -> TokenStream {
+    let accessors = item.fields.iter().enumerate().map(|(index, field)| {
+        let (member, stem) = field_names(index, field);
+        let ty = &field.ty;
+        let addr = format_ident!("{}_addr", stem.unraw());
+        let from_addr = format_ident!("{}_from_addr", stem.unraw());
+
+        quote! {
+            /// The field's address, with its provenance exposed for a later `_from_addr`.
+            pub unsafe fn #addr(&self) -> usize {
+                (&raw const self.#member).expose_provenance()
+            }
+
+            /// Rebuilds a pointer to the field from an exposed address; nothing checks the
+            /// address came from the matching `_addr` or that the value is still alive.
+            pub unsafe fn #from_addr(addr: usize) -> *mut #ty {
+                ::core::ptr::with_exposed_provenance_mut(addr)
+            }
+        }
+    });
+
+    quote! { #(#accessors)* }
+}
+
+fn extend_lifetime() -> TokenStream {
+    quote! {
+        /// Launders the borrow into `&'static Self`. Nothing keeps the value alive or stops it
+        /// being mutated or dropped while the result exists, so this is only sound for values
+        /// that already live forever, e.g. ones from `Box::leak`.
+        // Only the lifetime differs; clippy sees a transmute to the same type
+        #[allow(clippy::useless_transmute)]
+        pub unsafe fn as_ref_static(&self) -> &'static Self
+        where
+            Self: 'static,
+        {
+            unsafe { ::core::mem::transmute::<&Self, &'static Self>(self) }
+        }
+    }
+}
+
+// `WireHeader` -> `wire_header`, for naming generated free functions
+pub(crate) fn snake_case(ident: &Ident) -> String {
+    let mut snake = String::new();
+
+    for (index, ch) in ident.unraw().to_string().chars().enumerate() {
+        if ch.is_uppercase() && index > 0 {
+            snake.push('_');
+        }
+        snake.extend(ch.to_lowercase());
+    }
+
+    snake
+}
+
+// A `#[test]` comparing the layout against `size`/`align`, or `like` another type, so a
+// mismatch fails one test rather than the whole build
+fn validate_layout(item: &ItemStruct, expected: &Args) -> syn::Result<TokenStream> {
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.generics,
+            "#[noble(validate_layout)] can't pick generic arguments to check, so it needs a non-generic struct",
+        ));
+    }
+
+    let name = &item.ident;
+    let mut checks = Vec::new();
+
+    if let Some(like) = expected.value::<Type>("like")? {
+        checks.push(quote! {
+            assert_eq!(::core::mem::size_of::<#name>(), ::core::mem::size_of::<#like>(), "size of {}", stringify!(#name));
+            assert_eq!(::core::mem::align_of::<#name>(), ::core::mem::align_of::<#like>(), "align of {}", stringify!(#name));
+        });
+    }
+
+    if let Some(size) = expected.value::<LitInt>("size")? {
+        checks.push(quote! {
+            assert_eq!(::core::mem::size_of::<#name>(), #size, "size of {}", stringify!(#name));
+        });
+    }
+
+    if let Some(align) = expected.value::<LitInt>("align")? {
+        checks.push(quote! {
+            assert_eq!(::core::mem::align_of::<#name>(), #align, "align of {}", stringify!(#name));
+        });
+    }
+
+    if checks.is_empty() {
+        return Err(syn::Error::new_spanned(
+            name,
+            "#[noble(validate_layout)] needs `size = N`, `align = N` or `like = \"Type\"`",
+        ));
+    }
+
+    let test_name = format_ident!("{}_layout_check", snake_case(name));
+
+    Ok(quote! {
+        #[cfg(test)]
+        #[test]
+        fn #test_name() {
+            #(#checks)*
+        }
+    })
+}
+
+// Padding bytes are uninitialized, so reading them (or writing an invalid value for a field)
+// is UB; this is only sound for padding-free plain-old-data
+fn as_bytes() -> TokenStream {
+    quote! {
+        /// Views the value as its raw bytes, padding included.
+        pub unsafe fn as_bytes(&self) -> &[u8] {
+            unsafe {
+                ::core::slice::from_raw_parts(
+                    (self as *const Self).cast::<u8>(),
+                    ::core::mem::size_of::<Self>(),
+                )
+            }
+        }
+
+        /// Views the value as its raw bytes for writing; any bytes written must form a valid value.
+        pub unsafe fn as_bytes_mut(&mut self) -> &mut [u8] {
+            unsafe {
+                ::core::slice::from_raw_parts_mut(
+                    (self as *mut Self).cast::<u8>(),
+                    ::core::mem::size_of::<Self>(),
+                )
+            }
+        }
+    }
+}
+
+// The size has to be spelled out, an array length can't mention `size_of::<Self>()` on
+// stable. A wrong one fails `transmute`'s own size check at compile time.
+fn byte_array(item: &ItemStruct, options: &Args) -> syn::Result<TokenStream> {
+    let Some(size) = options.value::<syn::Expr>("size")? else {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(byte_array)] needs `size = N`, the struct's size in bytes",
+        ));
+    };
+
+    Ok(quote! {
+        /// The value's bytes, padding included, as an array.
+        pub unsafe fn to_array(self) -> [u8; #size] {
+            unsafe { ::core::mem::transmute::<Self, [u8; #size]>(self) }
+        }
+
+        /// Reinterprets the bytes as a value; they must form a valid one.
+        pub unsafe fn from_array(array: [u8; #size]) -> Self {
+            unsafe { ::core::mem::transmute::<[u8; #size], Self>(array) }
+        }
+    })
+}
+
+// `<a>_as_<b>(&self) -> B` for every pair of fields whose primitive types differ but have
+// the same size, reading one field's bits as the other's type
+fn union_cast(item: &ItemStruct) -> syn::Result<TokenStream> {
+    let sized: Vec<_> = item
+        .fields
+        .iter()
+        .enumerate()
+        .filter_map(|(index, field)| {
+            Some((field_names(index, field), field, ty::fixed_size(&field.ty)?))
+        })
+        .collect();
+
+    let mut casts = Vec::new();
+
+    for ((member, from), field, size) in &sized {
+        for ((_, to), other, other_size) in &sized {
+            if size != other_size || field.ty == other.ty {
+                continue;
+            }
+
+            let method = format_ident!("{}_as_{}", from.unraw(), to.unraw());
+            let (source, target) = (&field.ty, &other.ty);
+            let doc = format!(
+                "The bits of `{}` read as the type of `{}`, which they must be valid for.",
+                from.unraw(),
+                to.unraw()
+            );
+
+            casts.push(quote! {
+                #[doc = #doc]
+                pub unsafe fn #method(&self) -> #target {
+                    unsafe { ::core::mem::transmute::<#source, #target>(self.#member) }
+                }
+            });
+        }
+    }
+
+    if casts.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(union_cast)] needs two fields of different primitive types with the same size",
+        ));
+    }
+
+    Ok(quote! { #(#casts)* })
+}
+
+// Same view as `as_bytes`, but behind a safe trait method. Padding bytes are
+// uninitialized, so reading them through the slice is UB for anything but padding-free
+// plain-old-data
+fn borrow_bytes(item: &ItemStruct) -> TokenStream {
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::core::borrow::Borrow<[u8]> for #name #ty_generics #where_clause {
+            fn borrow(&self) -> &[u8] {
+                unsafe {
+                    ::core::slice::from_raw_parts(
+                        (self as *const Self).cast::<u8>(),
+                        ::core::mem::size_of::<Self>(),
+                    )
+                }
+            }
+        }
+    }
+}
+
+// Equality and ordering of the raw bytes, compared lexicographically like `[u8]` does.
+// Padding is compared too, and reading it is UB, so this is for padding-free POD only
+fn ord_bytes(item: &ItemStruct) -> syn::Result<TokenStream> {
+    for derived in ["PartialEq", "Eq", "PartialOrd", "Ord"] {
+        if let Some(derive) = attrs::derives(&item.attrs, derived)? {
+            return Err(syn::Error::new_spanned(
+                derive,
+                format!(
+                    "#[noble(ord_bytes)] generates `{}`, drop the derive",
+                    derived
+                ),
+            ));
+        }
+    }
+
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+    let bytes = |value: TokenStream| {
+        quote! {
+            unsafe {
+                ::core::slice::from_raw_parts(
+                    (#value as *const Self).cast::<u8>(),
+                    ::core::mem::size_of::<Self>(),
+                )
+            }
+        }
+    };
+    let (ours, theirs) = (bytes(quote! { self }), bytes(quote! { other }));
+
+    Ok(quote! {
+        impl #impl_generics ::core::cmp::Ord for #name #ty_generics #where_clause {
+            fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                let ours: &[u8] = #ours;
+                let theirs: &[u8] = #theirs;
+                ours.cmp(theirs)
+            }
+        }
+
+        impl #impl_generics ::core::cmp::PartialOrd for #name #ty_generics #where_clause {
+            fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+                ::core::option::Option::Some(::core::cmp::Ord::cmp(self, other))
+            }
+        }
+
+        impl #impl_generics ::core::cmp::Eq for #name #ty_generics #where_clause {}
+
+        impl #impl_generics ::core::cmp::PartialEq for #name #ty_generics #where_clause {
+            fn eq(&self, other: &Self) -> bool {
+                ::core::cmp::Ord::cmp(self, other).is_eq()
+            }
+        }
+    })
+}
+
+// A `<Name>Iter` following the one `*const Self`/`*mut Self` field from node to node
+// until it is null, yielding each node on the way
+fn linked_iter(item: &ItemStruct) -> syn::Result<TokenStream> {
+    let name = &item.ident;
+    let mut links = item.fields.iter().enumerate().filter(|(_, field)| {
+        ty::pointee(&field.ty)
+            .and_then(ty::path_of)
+            .and_then(|path| path.segments.last())
+            .is_some_and(|segment| segment.ident == "Self" || segment.ident == *name)
+    });
+
+    let Some((index, field)) = links.next() else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "#[noble(linked_iter)] needs a `*const Self` or `*mut Self` field",
+        ));
+    };
+
+    if let Some((_, extra)) = links.next() {
+        return Err(syn::Error::new_spanned(
+            extra,
+            "#[noble(linked_iter)] supports exactly one link field",
+        ));
+    }
+
+    let (member, _) = field_names(index, field);
+    let vis = &item.vis;
+    let iter_name = format_ident!("{}Iter", name);
+    let doc = format!(
+        "Walks the `{}` chain started by [`{}::linked_iter`].",
+        name, name
+    );
+
+    let lifetime = Lifetime::new("'noble_iter", proc_macro2::Span::call_site());
+    let mut iter_generics = item.generics.clone();
+    iter_generics.params.insert(
+        0,
+        GenericParam::Lifetime(syn::LifetimeParam::new(lifetime.clone())),
+    );
+
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+    let (iter_impl_generics, iter_ty_generics, _) = iter_generics.split_for_impl();
+
+    Ok(quote! {
+        #[doc = #doc]
+        #vis struct #iter_name #iter_impl_generics #where_clause {
+            next: *const #name #ty_generics,
+            _nodes: ::core::marker::PhantomData<&#lifetime #name #ty_generics>,
+        }
+
+        impl #iter_impl_generics ::core::iter::Iterator for #iter_name #iter_ty_generics #where_clause {
+            type Item = &#lifetime #name #ty_generics;
+
+            fn next(&mut self) -> ::core::option::Option<Self::Item> {
+                if self.next.is_null() {
+                    return ::core::option::Option::None;
+                }
+
+                let node = unsafe { &*self.next };
+                self.next = node.#member as *const #name #ty_generics;
+                ::core::option::Option::Some(node)
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Iterates from this node along the links, trusting every non-null one is a live node.
+            pub unsafe fn linked_iter<#lifetime>(&#lifetime self) -> #iter_name #iter_ty_generics {
+                #iter_name {
+                    next: self,
+                    _nodes: ::core::marker::PhantomData,
+                }
+            }
+        }
+    })
+}
+
+fn split_mut(item: &ItemStruct) -> syn::Result<TokenStream> {
+    if item.fields.len() < 2 {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(split_mut)] needs at least two fields to split",
+        ));
+    }
+
+    let members = item
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| field_names(index, field).0);
+    let types = item.fields.iter().map(|field| &field.ty);
+
+    // Disjoint fields never alias, which is why the borrow checker allows this for direct
+    // field access; going through a raw pointer hides even that from it
+    Ok(quote! {
+        /// Borrows every field mutably at once, in declaration order.
+        pub unsafe fn split_mut(&mut self) -> (#(&mut #types),*) {
+            let this: *mut Self = self;
+            unsafe { (#(&mut (*this).#members),*) }
+        }
+    })
+}
+
+// Tuple fields answer to their index, so `"0"` finds the first one
+fn index_by_name(item: &ItemStruct) -> TokenStream {
+    let arms = item.fields.iter().enumerate().map(|(index, field)| {
+        let (member, _) = field_names(index, field);
+        let name = match &field.ident {
+            Some(ident) => ident.unraw().to_string(),
+            None => index.to_string(),
+        };
+
+        quote! {
+            #name => ::core::mem::offset_of!(Self, #member),
+        }
+    });
+
+    quote! {
+        /// A type-erased pointer to the field called `name`, or null if there is none; reading
+        /// it as the wrong type is on you.
+        pub unsafe fn get_by_name(&self, name: &str) -> *const () {
+            let offset = match name {
+                #(#arms)*
+                _ => return ::core::ptr::null(),
+            };
+
+            unsafe { (self as *const Self).cast::<u8>().add(offset).cast() }
+        }
+    }
+}
+
+// `ptr`/`len`/`cap` fields describing a `Vec`'s allocation; anything else is defaulted
+fn as_vec(item: &ItemStruct) -> syn::Result<TokenStream> {
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(as_vec)] needs noble's `std` feature",
+        ));
+    }
+
+    let (ptr, _, elem) = pointer_field(item, "as_vec")?;
+
+    let named = |names: &[&str]| {
+        item.fields.iter().find_map(|field| {
+            let ident = field.ident.as_ref()?;
+            names.iter().any(|name| ident == name).then_some(ident)
+        })
+    };
+    let (Some(len), Some(cap)) = (named(&["len", "length"]), named(&["cap", "capacity"])) else {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(as_vec)] needs `len` and `cap` fields next to the pointer",
+        ));
+    };
+
+    let inits = item.fields.iter().enumerate().map(|(index, field)| {
+        let (member, _) = field_names(index, field);
+        let value = if member == ptr {
+            quote! { vec.as_mut_ptr() }
+        } else if field.ident.as_ref() == Some(len) {
+            quote! { vec.len() }
+        } else if field.ident.as_ref() == Some(cap) {
+            quote! { vec.capacity() }
+        } else {
+            quote! { ::core::default::Default::default() }
+        };
+
+        quote! { #member: #value }
+    });
+
+    Ok(quote! {
+        /// Reassembles the `Vec` these parts describe; they must have come from one.
+        pub unsafe fn as_vec(self) -> ::std::vec::Vec<#elem> {
+            let this = ::core::mem::ManuallyDrop::new(self);
+            unsafe { ::std::vec::Vec::from_raw_parts(this.#ptr as *mut #elem, this.#len, this.#cap) }
+        }
+
+        /// Takes the `Vec` apart without freeing it; [`Self::as_vec`] puts it back together.
+        pub unsafe fn from_vec(vec: ::std::vec::Vec<#elem>) -> Self {
+            let mut vec = ::core::mem::ManuallyDrop::new(vec);
+            Self {
+                #(#inits,)*
+            }
+        }
+    })
+}
+
+fn deref_raw(item: &ItemStruct) -> syn::Result<TokenStream> {
+    let (member, _, pointee) = pointer_field(item, "deref_raw")?;
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    // A safe `*` on the wrapper now dereferences whatever the pointer holds
+    Ok(quote! {
+        impl #impl_generics ::core::ops::Deref for #name #ty_generics #where_clause {
+            type Target = #pointee;
+
+            fn deref(&self) -> &Self::Target {
+                unsafe { &*self.#member }
+            }
+        }
+    })
+}
+
+// `Write` appends at `len` with no capacity check, `Read` hands out the bytes between `pos`
+// and `len`
+fn io(item: &ItemStruct) -> syn::Result<TokenStream> {
+    if !cfg!(feature = "std") {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(io)] needs noble's `std` feature",
+        ));
+    }
+
+    let (buffer, field, pointee) = pointer_field(item, "io")?;
+    let is_mut_u8 = matches!(&field.ty, Type::Ptr(ptr) if ptr.mutability.is_some())
+        && ty::path_of(pointee).is_some_and(|path| path.is_ident("u8"));
+    if !is_mut_u8 {
+        return Err(syn::Error::new_spanned(
+            &field.ty,
+            "#[noble(io)] needs the buffer to be a `*mut u8`",
+        ));
+    }
+
+    let named = |names: &[&str]| {
+        item.fields.iter().find_map(|field| {
+            let ident = field.ident.as_ref()?;
+            names.iter().any(|name| ident == name).then_some(ident)
+        })
+    };
+    let (Some(len), Some(pos)) = (named(&["len", "length"]), named(&["pos", "cursor"])) else {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(io)] needs `len` and `pos` fields next to the buffer",
+        ));
+    };
+
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    // Writes past the end of the allocation corrupt whatever follows it
+    Ok(quote! {
+        impl #impl_generics ::std::io::Write for #name #ty_generics #where_clause {
+            fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+                unsafe {
+                    ::core::ptr::copy_nonoverlapping(
+                        buf.as_ptr(),
+                        self.#buffer.add(self.#len),
+                        buf.len(),
+                    );
+                }
+                self.#len += buf.len();
+                ::core::result::Result::Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> ::std::io::Result<()> {
+                ::core::result::Result::Ok(())
+            }
+        }
+
+        impl #impl_generics ::std::io::Read for #name #ty_generics #where_clause {
+            fn read(&mut self, buf: &mut [u8]) -> ::std::io::Result<usize> {
+                let count = buf.len().min(self.#len.saturating_sub(self.#pos));
+                unsafe {
+                    ::core::ptr::copy_nonoverlapping(
+                        self.#buffer.add(self.#pos),
+                        buf.as_mut_ptr(),
+                        count,
+                    );
+                }
+                self.#pos += count;
+                ::core::result::Result::Ok(count)
+            }
+        }
+    })
+}
+
+// `AsRef<T>`, plus `AsMut<T>` when the field is a `*mut T`, both dereferencing the pointer
+// from safe code
+fn as_ref_raw(item: &ItemStruct) -> syn::Result<TokenStream> {
+    let (member, field, pointee) = pointer_field(item, "as_ref_raw")?;
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+
+    let as_mut = matches!(&field.ty, Type::Ptr(ptr) if ptr.mutability.is_some()).then(|| {
+        quote! {
+            impl #impl_generics ::core::convert::AsMut<#pointee> for #name #ty_generics #where_clause {
+                fn as_mut(&mut self) -> &mut #pointee {
+                    unsafe { &mut *self.#member }
+                }
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::core::convert::AsRef<#pointee> for #name #ty_generics #where_clause {
+            fn as_ref(&self) -> &#pointee {
+                unsafe { &*self.#member }
+            }
+        }
+
+        #as_mut
+    })
+}
+
+// A `<Name>SendSync` newtype claiming `Send` and `Sync` for the whole struct, with a
+// `call_<field>` forwarding to each boxed closure field
+fn send_sync_closure(item: &ItemStruct) -> syn::Result<TokenStream> {
+    let mut calls = Vec::new();
+
+    for (index, field) in item.fields.iter().enumerate() {
+        let Some((kind, closure)) = ty::boxed_closure(&field.ty) else {
+            continue;
+        };
+
+        let (member, stem) = field_names(index, field);
+        let method = format_ident!("call_{}", stem.unraw());
+        let receiver = match kind.to_string().as_str() {
+            "Fn" => quote! { &self },
+            "FnMut" => quote! { &mut self },
+            _ => quote! { self },
+        };
+        let arg_types: Vec<_> = closure.inputs.iter().collect();
+        let arg_names: Vec<_> = (0..arg_types.len())
+            .map(|index| format_ident!("arg{}", index))
+            .collect();
+        let output = &closure.output;
+
+        calls.push(quote! {
+            /// Calls the closure field, on whichever thread now holds the wrapper.
+            pub fn #method(#receiver #(, #arg_names: #arg_types)*) #output {
+                (self.0.#member)(#(#arg_names),*)
+            }
+        });
+    }
+
+    if calls.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(send_sync_closure)] needs a `Box<dyn Fn(..)>` field",
+        ));
+    }
+
+    let name = &item.ident;
+    let vis = &item.vis;
+    let wrapper = format_ident!("{}SendSync", name);
+    let doc = format!(
+        "`{}` marked `Send` and `Sync` whether or not its closures are.",
+        name
+    );
+    let generics = &item.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        #[doc = #doc]
+        #vis struct #wrapper #generics (#vis #name #ty_generics) #where_clause;
+
+        unsafe impl #impl_generics ::core::marker::Send for #wrapper #ty_generics #where_clause {}
+
+        unsafe impl #impl_generics ::core::marker::Sync for #wrapper #ty_generics #where_clause {}
+
+        impl #impl_generics #wrapper #ty_generics #where_clause {
+            /// Wraps the value, promising its closures are fine to send and share across
+            /// threads even though their types don't say so.
+            pub unsafe fn new(value: #name #ty_generics) -> Self {
+                Self(value)
+            }
+
+            /// Unwraps the value, bringing back the closures' real auto traits.
+            pub fn into_inner(self) -> #name #ty_generics {
+                self.0
+            }
+
+            #(#calls)*
+        }
+    })
+}
+
+// A `<Name>Inline` with room for `cap` elements of the one sequence field's type, stored in
+// place. Nothing checks the capacity or the bounds, that is the point
+fn inline_buffer(item: &ItemStruct, options: &Args) -> syn::Result<TokenStream> {
+    let Some(cap) = options.value::<syn::Expr>("cap")? else {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(inline_buffer)] needs `cap = N`, the number of inline elements",
+        ));
+    };
+
+    let mut sequences = item
+        .fields
+        .iter()
+        .filter_map(|field| Some((field, ty::element_type(&field.ty)?)));
+
+    let Some((_, elem)) = sequences.next() else {
+        return Err(syn::Error::new_spanned(
+            &item.ident,
+            "#[noble(inline_buffer)] needs a `Vec<T>`, `Box<[T]>`, `[T; N]` or `[T]` field",
+        ));
+    };
+
+    if let Some((extra, _)) = sequences.next() {
+        return Err(syn::Error::new_spanned(
+            extra,
+            "#[noble(inline_buffer)] supports exactly one sequence field",
+        ));
+    }
+
+    let name = &item.ident;
+    let vis = &item.vis;
+    let buffer = format_ident!("{}Inline", name);
+    let doc = format!(
+        "Up to `{}` elements of `{}`'s sequence stored inline, without capacity or bounds checks.",
+        cap.to_token_stream(),
+        name
+    );
+    let generics = &item.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        #[doc = #doc]
+        #vis struct #buffer #generics #where_clause {
+            items: [::core::mem::MaybeUninit<#elem>; #cap],
+            len: usize,
+            // The element type may not use every parameter of the struct
+            _marker: ::core::marker::PhantomData<fn() -> #name #ty_generics>,
+        }
+
+        impl #impl_generics #buffer #ty_generics #where_clause {
+            /// How many elements fit; pushing past it writes out of bounds.
+            pub const CAPACITY: usize = #cap;
+
+            /// An empty buffer.
+            pub const fn new() -> Self {
+                Self {
+                    items: [const { ::core::mem::MaybeUninit::uninit() }; #cap],
+                    len: 0,
+                    _marker: ::core::marker::PhantomData,
+                }
+            }
+
+            /// Appends without checking there is room. Pushing a full buffer is UB.
+            pub unsafe fn push_unchecked(&mut self, value: #elem) {
+                unsafe { self.items.get_unchecked_mut(self.len).write(value) };
+                self.len += 1;
+            }
+
+            /// The element at `index`, which must be below `len()`.
+            pub unsafe fn get_unchecked(&self, index: usize) -> &#elem {
+                unsafe { self.items.get_unchecked(index).assume_init_ref() }
+            }
+
+            /// How many elements have been pushed.
+            pub fn len(&self) -> usize {
+                self.len
+            }
+
+            /// Whether nothing has been pushed yet.
+            pub fn is_empty(&self) -> bool {
+                self.len == 0
+            }
+
+            /// The elements pushed so far.
+            pub fn as_slice(&self) -> &[#elem] {
+                unsafe { ::core::slice::from_raw_parts(self.items.as_ptr().cast(), self.len) }
+            }
+        }
+
+        impl #impl_generics ::core::ops::Drop for #buffer #ty_generics #where_clause {
+            fn drop(&mut self) {
+                unsafe {
+                    ::core::ptr::drop_in_place(::core::ptr::slice_from_raw_parts_mut(
+                        self.items.as_mut_ptr().cast::<#elem>(),
+                        self.len,
+                    ));
+                }
+            }
+        }
+    })
+}
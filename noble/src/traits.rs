@@ -0,0 +1,339 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{ToTokens, format_ident, quote};
+use syn::{FnArg, GenericParam, ItemTrait, ReturnType, TraitItem};
+
+use crate::args::Args;
+
+pub(crate) const FLAGS: &[&str] = &["vtable", "blanket", "impl_for_refs"];
+
+// Extra items requested through `#[noble(...)]` flags on a trait
+pub(crate) fn helpers(args: &Args, item: &ItemTrait) -> syn::Result<TokenStream> {
+    let mut items = Vec::new();
+
+    if args.flag("vtable") {
+        items.push(vtable(item)?);
+    }
+
+    if args.flag("blanket") {
+        items.push(blanket(item)?);
+    }
+
+    if args.flag("impl_for_refs") {
+        items.push(impl_for_refs(item)?);
+    }
+
+    Ok(quote! { #(#items)* })
+}
+
+// A `#[repr(C)]` table of `extern "C"` shims per method, and a handle dispatching through it
+fn vtable(item: &ItemTrait) -> syn::Result<TokenStream> {
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.generics,
+            "#[noble(vtable)] doesn't support generic traits",
+        ));
+    }
+
+    let trait_name = &item.ident;
+    let vis = &item.vis;
+    let vtable_name = format_ident!("{}Vtable", trait_name);
+    let handle_name = format_ident!("{}Dyn", trait_name);
+
+    let mut slots = Vec::new();
+    let mut shims = Vec::new();
+    let mut calls = Vec::new();
+
+    for trait_item in &item.items {
+        let TraitItem::Fn(method) = trait_item else {
+            continue;
+        };
+        let sig = &method.sig;
+
+        if !sig.generics.params.is_empty() || sig.asyncness.is_some() {
+            return Err(syn::Error::new_spanned(
+                sig,
+                "#[noble(vtable)] methods can't be generic or async",
+            ));
+        }
+
+        let receiver = match sig.receiver() {
+            Some(receiver) if receiver.reference.is_some() => receiver,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    sig,
+                    "#[noble(vtable)] methods need a `&self` or `&mut self` receiver",
+                ));
+            }
+        };
+        let this = if receiver.mutability.is_some() {
+            quote! { &mut *data.cast::<T>() }
+        } else {
+            quote! { &*data.cast::<T>() }
+        };
+
+        let name = &sig.ident;
+        let output = match &sig.output {
+            ReturnType::Default => quote! {},
+            ReturnType::Type(arrow, ty) => quote! { #arrow #ty },
+        };
+        let (arg_names, arg_types): (Vec<_>, Vec<_>) = sig
+            .inputs
+            .iter()
+            .filter_map(|input| match input {
+                FnArg::Typed(pat_type) => Some(&pat_type.ty),
+                FnArg::Receiver(_) => None,
+            })
+            .enumerate()
+            .map(|(index, ty)| (format_ident!("arg{}", index), ty))
+            .unzip();
+
+        slots.push(quote! {
+            pub #name: unsafe extern "C" fn(*mut () #(, #arg_types)*) #output
+        });
+
+        shims.push(quote! {
+            #name: {
+                unsafe extern "C" fn #name<T: #trait_name>(data: *mut () #(, #arg_names: #arg_types)*) #output {
+                    unsafe { <T as #trait_name>::#name(#this #(, #arg_names)*) }
+                }
+                #name::<T>
+            }
+        });
+
+        calls.push(quote! {
+            pub unsafe fn #name(&self #(, #arg_names: #arg_types)*) #output {
+                unsafe { (self.vtable.#name)(self.data #(, #arg_names)*) }
+            }
+        });
+    }
+
+    Ok(quote! {
+        #[repr(C)]
+        #[allow(improper_ctypes_definitions)]
+        #vis struct #vtable_name {
+            #(#slots,)*
+        }
+
+        impl #vtable_name {
+            /// The table for `T`, whose `data` pointers must point at a live `T`.
+            #[allow(improper_ctypes_definitions)]
+            pub const fn of<T: #trait_name>() -> Self {
+                Self {
+                    #(#shims,)*
+                }
+            }
+        }
+
+        #vis struct #handle_name<'vtable> {
+            data: *mut (),
+            vtable: &'vtable #vtable_name,
+        }
+
+        impl<'vtable> #handle_name<'vtable> {
+            /// Pairs a type-erased pointer with a table; nothing checks they belong together.
+            pub unsafe fn from_ptr(data: *mut (), vtable: &'vtable #vtable_name) -> Self {
+                Self { data, vtable }
+            }
+
+            #(#calls)*
+        }
+    })
+}
+
+// Implements the trait for every (sized) type, relying on the default bodies. Any other impl
+// of the trait now overlaps with this one, which the compiler will point out.
+fn blanket(item: &ItemTrait) -> syn::Result<TokenStream> {
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.generics,
+            "#[noble(blanket)] doesn't support generic traits",
+        ));
+    }
+
+    for trait_item in &item.items {
+        match trait_item {
+            TraitItem::Fn(method) if method.default.is_none() => {
+                return Err(syn::Error::new_spanned(
+                    &method.sig,
+                    "#[noble(blanket)] needs a default body for every method",
+                ));
+            }
+            TraitItem::Type(ty) if ty.default.is_none() => {
+                return Err(syn::Error::new_spanned(
+                    ty,
+                    "#[noble(blanket)] can't pick a type for associated types without a default",
+                ));
+            }
+            TraitItem::Const(constant) if constant.default.is_none() => {
+                return Err(syn::Error::new_spanned(
+                    constant,
+                    "#[noble(blanket)] needs a default value for every associated const",
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    let trait_name = &item.ident;
+    let supertraits = &item.supertraits;
+    let bounds =
+        (!supertraits.is_empty()).then(|| quote! { where NobleBlanketTarget: #supertraits });
+
+    Ok(quote! {
+        unsafe impl<NobleBlanketTarget> #trait_name for NobleBlanketTarget #bounds {}
+    })
+}
+
+// Implements the trait for `&mut T`, and for `&T` when no method needs `&mut self`, with every
+// item forwarding to `T`'s impl
+fn impl_for_refs(item: &ItemTrait) -> syn::Result<TokenStream> {
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.generics,
+            "#[noble(impl_for_refs)] doesn't support generic traits",
+        ));
+    }
+
+    let trait_name = &item.ident;
+    let mut shared = Vec::new();
+    let mut exclusive = Vec::new();
+    let mut shared_possible = true;
+
+    for trait_item in &item.items {
+        match trait_item {
+            TraitItem::Fn(method) => {
+                let mut sig = method.sig.clone();
+
+                let receiver = match sig.receiver() {
+                    Some(receiver) if receiver.reference.is_some() => Some(receiver),
+                    Some(_) => {
+                        return Err(syn::Error::new_spanned(
+                            &method.sig,
+                            "#[noble(impl_for_refs)] can't forward a method taking `self` by value",
+                        ));
+                    }
+                    None => None,
+                };
+                if receiver.is_some_and(|receiver| receiver.mutability.is_some()) {
+                    shared_possible = false;
+                }
+                let this = receiver.map(|receiver| match receiver.mutability {
+                    Some(_) => quote! { &mut **self, },
+                    None => quote! { &**self, },
+                });
+
+                if mentions_self(&sig.output) {
+                    return Err(syn::Error::new_spanned(
+                        &sig.output,
+                        "#[noble(impl_for_refs)] can't forward a method returning `Self`",
+                    ));
+                }
+
+                let mut forwarded = Vec::new();
+                for (index, input) in sig.inputs.iter_mut().enumerate() {
+                    let FnArg::Typed(pat_type) = input else {
+                        continue;
+                    };
+                    if mentions_self(&pat_type.ty) {
+                        return Err(syn::Error::new_spanned(
+                            &pat_type.ty,
+                            "#[noble(impl_for_refs)] can't forward a `Self` argument",
+                        ));
+                    }
+
+                    let arg = format_ident!("arg{}", index);
+                    *pat_type.pat = syn::parse_quote! { #arg };
+                    forwarded.push(arg);
+                }
+
+                let turbofish: Vec<_> = sig
+                    .generics
+                    .params
+                    .iter()
+                    .filter_map(|param| match param {
+                        GenericParam::Type(param) => Some(&param.ident),
+                        GenericParam::Const(param) => Some(&param.ident),
+                        GenericParam::Lifetime(_) => None,
+                    })
+                    .collect();
+                let turbofish = (!turbofish.is_empty()).then(|| quote! { ::<#(#turbofish),*> });
+                let name = &sig.ident;
+                let await_call = sig.asyncness.map(|_| quote! { .await });
+
+                let forwarding = quote! {
+                    #sig {
+                        unsafe {
+                            <NobleRefTarget as #trait_name>::#name #turbofish (#this #(#forwarded),*) #await_call
+                        }
+                    }
+                };
+                shared.push(forwarding.clone());
+                exclusive.push(forwarding);
+            }
+            TraitItem::Type(ty) => {
+                if !ty.generics.params.is_empty() {
+                    return Err(syn::Error::new_spanned(
+                        ty,
+                        "#[noble(impl_for_refs)] doesn't support generic associated types",
+                    ));
+                }
+
+                let name = &ty.ident;
+                let forwarding = quote! {
+                    type #name = <NobleRefTarget as #trait_name>::#name;
+                };
+                shared.push(forwarding.clone());
+                exclusive.push(forwarding);
+            }
+            TraitItem::Const(constant) => {
+                let name = &constant.ident;
+                let ty = &constant.ty;
+                let forwarding = quote! {
+                    const #name: #ty = <NobleRefTarget as #trait_name>::#name;
+                };
+                shared.push(forwarding.clone());
+                exclusive.push(forwarding);
+            }
+            _ => {}
+        }
+    }
+
+    let shared_impl = shared_possible.then(|| {
+        quote! {
+            unsafe impl<NobleRefTarget: #trait_name + ?Sized> #trait_name for &NobleRefTarget {
+                #(#shared)*
+            }
+        }
+    });
+
+    Ok(quote! {
+        #shared_impl
+
+        unsafe impl<NobleRefTarget: #trait_name + ?Sized> #trait_name for &mut NobleRefTarget {
+            #(#exclusive)*
+        }
+    })
+}
+
+// `Self` means the reference in the forwarding impl, so it can't stand for `T` anymore.
+// `Self::Assoc` is fine, the forwarded associated types are `T`'s.
+fn mentions_self(tokens: impl ToTokens) -> bool {
+    let mut trees = tokens.into_token_stream().into_iter().peekable();
+
+    while let Some(tree) = trees.next() {
+        let found = match tree {
+            TokenTree::Ident(ident) => {
+                ident == "Self"
+                    && !matches!(trees.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == ':')
+            }
+            TokenTree::Group(group) => mentions_self(group.stream()),
+            _ => false,
+        };
+
+        if found {
+            return true;
+        }
+    }
+
+    false
+}
@@ -0,0 +1,173 @@
+use quote::format_ident;
+use syn::{
+    GenericArgument, Ident, ParenthesizedGenericArguments, Path, PathArguments, Type,
+    TypeParamBound,
+};
+
+pub(crate) fn path_of(ty: &Type) -> Option<&Path> {
+    match ty {
+        Type::Path(type_path) if type_path.qself.is_none() => Some(&type_path.path),
+        Type::Group(group) => path_of(&group.elem),
+        Type::Paren(paren) => path_of(&paren.elem),
+        _ => None,
+    }
+}
+
+// The type arguments of `ty` when its last path segment is `name`, e.g. `T` for `Weak<T>`
+pub(crate) fn type_args<'a>(ty: &'a Type, name: &str) -> Option<Vec<&'a Type>> {
+    let segment = path_of(ty)?.segments.last()?;
+    if segment.ident != name {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => Some(
+            args.args
+                .iter()
+                .filter_map(|arg| match arg {
+                    GenericArgument::Type(ty) => Some(ty),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+const INTEGERS: &[&str] = &[
+    "u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize",
+];
+
+// `AtomicU32` for `u32` and so on, for the integer types that have an atomic twin
+pub(crate) fn atomic_twin(ty: &Type) -> Option<Ident> {
+    let ident = path_of(ty)?.get_ident()?.to_string();
+    if !INTEGERS.contains(&ident.as_str()) {
+        return None;
+    }
+
+    // `u32` -> `U32`, `usize` -> `Usize`
+    let (signedness, width) = ident.split_at(1);
+
+    Some(format_ident!(
+        "Atomic{}{}",
+        signedness.to_uppercase(),
+        width
+    ))
+}
+
+// Sizes that are the same on every target. Pointer-sized types are left out, the macro
+// can't know the target's width.
+const FIXED_SIZES: &[(&str, usize)] = &[
+    ("u8", 1),
+    ("i8", 1),
+    ("bool", 1),
+    ("u16", 2),
+    ("i16", 2),
+    ("u32", 4),
+    ("i32", 4),
+    ("f32", 4),
+    ("char", 4),
+    ("u64", 8),
+    ("i64", 8),
+    ("f64", 8),
+    ("u128", 16),
+    ("i128", 16),
+];
+
+// The size of a primitive whose size doesn't depend on the target
+pub(crate) fn fixed_size(ty: &Type) -> Option<usize> {
+    let ident = path_of(ty)?.get_ident()?;
+
+    FIXED_SIZES
+        .iter()
+        .find(|(name, _)| ident == name)
+        .map(|(_, size)| *size)
+}
+
+// `T` for the sequence types `Vec<T>`, `Box<[T]>`, `[T; N]` and `[T]`
+pub(crate) fn element_type(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Array(array) => Some(&array.elem),
+        Type::Slice(slice) => Some(&slice.elem),
+        Type::Group(group) => element_type(&group.elem),
+        Type::Paren(paren) => element_type(&paren.elem),
+        _ => {
+            if let Some([elem]) = type_args(ty, "Vec").as_deref() {
+                return Some(elem);
+            }
+
+            match type_args(ty, "Box").as_deref() {
+                Some([Type::Slice(slice)]) => Some(&slice.elem),
+                _ => None,
+            }
+        }
+    }
+}
+
+// `T` for `*const T` and `*mut T`
+pub(crate) fn pointee(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Ptr(ptr) => Some(&ptr.elem),
+        Type::Group(group) => pointee(&group.elem),
+        Type::Paren(paren) => pointee(&paren.elem),
+        _ => None,
+    }
+}
+
+// `T` for `*mut T` only
+pub(crate) fn mut_pointee(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Ptr(ptr) if ptr.mutability.is_some() => Some(&ptr.elem),
+        Type::Group(group) => mut_pointee(&group.elem),
+        Type::Paren(paren) => mut_pointee(&paren.elem),
+        _ => None,
+    }
+}
+
+// `T` for `*const T`, `*mut T`, `&T` and `&mut T`
+pub(crate) fn points_to(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Reference(reference) => Some(&reference.elem),
+        _ => pointee(ty),
+    }
+}
+
+// Whether `ty` is a run of bytes: `Vec<u8>`, `&[u8]`, `Box<[u8]>` or `[u8; N]`
+pub(crate) fn is_bytes(ty: &Type) -> bool {
+    let ty = match ty {
+        Type::Reference(reference) => &reference.elem,
+        _ => ty,
+    };
+
+    element_type(ty)
+        .and_then(path_of)
+        .is_some_and(|path| path.is_ident("u8"))
+}
+
+// The `Fn`/`FnMut`/`FnOnce` trait and its `(Args) -> Output` for a `Box<dyn Fn..>`
+pub(crate) fn boxed_closure(ty: &Type) -> Option<(&Ident, &ParenthesizedGenericArguments)> {
+    let [inner] = type_args(ty, "Box")?[..] else {
+        return None;
+    };
+    let Type::TraitObject(object) = inner else {
+        return None;
+    };
+
+    object.bounds.iter().find_map(|bound| {
+        let TypeParamBound::Trait(bound) = bound else {
+            return None;
+        };
+        let segment = bound.path.segments.last()?;
+
+        match &segment.arguments {
+            PathArguments::Parenthesized(args)
+                if ["Fn", "FnMut", "FnOnce"]
+                    .iter()
+                    .any(|name| segment.ident == name) =>
+            {
+                Some((&segment.ident, args))
+            }
+            _ => None,
+        }
+    })
+}
@@ -0,0 +1,101 @@
+use noble::noble;
+
+#[repr(u8)]
+#[derive(Debug, PartialEq)]
+enum Mirror {
+    Off = 0,
+    On = 1,
+}
+
+#[noble(transmute_enum = "Mirror")]
+#[repr(u8)]
+enum Switch {
+    Off = 0,
+    On = 1,
+}
+
+#[test]
+fn transmute_enum_mirrors_matching_variants() {
+    assert_eq!(unsafe { Switch::On.as_other() }, Mirror::On);
+    assert_eq!(unsafe { Switch::Off.as_other() }, Mirror::Off);
+}
+
+#[noble(discriminant)]
+#[repr(u16)]
+enum Opcode {
+    Nop = 0x90,
+    Jump = 0xE9,
+    Halt = 0xF4,
+}
+
+#[test]
+fn discriminant_reads_declared_values() {
+    assert_eq!(unsafe { Opcode::Nop.discriminant() }, 0x90);
+    assert_eq!(unsafe { Opcode::Jump.discriminant() }, 0xE9);
+    assert_eq!(unsafe { Opcode::Halt.discriminant() }, 0xF4);
+}
+
+#[noble]
+#[non_exhaustive]
+#[derive(Debug, PartialEq)]
+enum Event {
+    Tick,
+    Key(char),
+    Resize { width: u32, height: u32 },
+}
+
+#[test]
+fn non_exhaustive_enums_construct_in_crate() {
+    assert_eq!(unsafe { Event::new_tick_unsafe() }, Event::Tick);
+    assert_eq!(unsafe { Event::new_key_unsafe('q') }, Event::Key('q'));
+    assert_eq!(
+        unsafe { Event::new_resize_unsafe(80, 24) },
+        Event::Resize {
+            width: 80,
+            height: 24
+        }
+    );
+}
+
+#[noble(from_discriminant, discriminant)]
+#[repr(i8)]
+#[derive(Debug, PartialEq)]
+enum Direction {
+    Back = -1,
+    Stay,
+    Forward,
+}
+
+#[test]
+fn from_discriminant_inverts_discriminant() {
+    assert_eq!(
+        unsafe { Direction::from_discriminant(1) },
+        Direction::Forward
+    );
+    assert_eq!(unsafe { Direction::from_discriminant(-1) }, Direction::Back);
+    assert_eq!(
+        unsafe { Direction::from_discriminant(Direction::Stay.discriminant()) },
+        Direction::Stay
+    );
+}
+
+#[noble(extract)]
+enum Reply<T> {
+    Data(T),
+    Tagged(T, u8),
+    Failed { code: u16, detail: T },
+}
+
+#[test]
+fn extract_moves_out_the_known_variant() {
+    let data = Reply::Data(String::from("payload"));
+    let tagged = Reply::Tagged(vec![1, 2], 7);
+    let failed = Reply::Failed {
+        code: 404,
+        detail: "missing",
+    };
+
+    assert_eq!(unsafe { data.into_data_unchecked() }, "payload");
+    assert_eq!(unsafe { tagged.into_tagged_unchecked() }, (vec![1, 2], 7));
+    assert_eq!(unsafe { failed.into_failed_unchecked() }, (404, "missing"));
+}
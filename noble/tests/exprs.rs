@@ -0,0 +1,55 @@
+use std::future::Future;
+use std::pin::pin;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::task::{Context, Poll, Waker};
+
+use noble::noble_expr;
+
+static CALLS: AtomicU32 = AtomicU32::new(0);
+
+/// # Safety
+///
+/// None needed, it only has to be `unsafe` to prove the call site was wrapped.
+unsafe fn ffi_call(value: u32) -> u32 {
+    CALLS.fetch_add(1, Ordering::SeqCst);
+    value * 2
+}
+
+// The futures here never wait, so one poll drives them to completion
+fn poll_once<F: Future>(future: F) -> F::Output {
+    match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("future was not ready"),
+    }
+}
+
+#[test]
+fn async_move_block_runs_unsafe_body() {
+    let before = CALLS.load(Ordering::SeqCst);
+    let input = String::from("21");
+
+    let future = noble_expr!(async move {
+        let value = std::future::ready(input.parse().unwrap()).await;
+        ffi_call(value)
+    });
+
+    assert_eq!(poll_once(future), 42);
+    assert!(CALLS.load(Ordering::SeqCst) > before);
+}
+
+#[test]
+fn closures_wrap_their_bodies() {
+    let offset = 1;
+    let plain = noble_expr!(move |value: u32| ffi_call(value) + offset);
+    let returning_async = noble_expr!(|value: u32| async move { ffi_call(value) });
+    let async_closure = noble_expr!(async move |value: u32| ffi_call(value));
+
+    assert_eq!(plain(4), 9);
+    assert_eq!(poll_once(returning_async(5)), 10);
+    assert_eq!(poll_once(async_closure(6)), 12);
+}
+
+#[test]
+fn plain_expressions_are_wrapped() {
+    assert_eq!(noble_expr!(ffi_call(8)), 16);
+}
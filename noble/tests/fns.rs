@@ -0,0 +1,398 @@
+use noble::noble;
+
+#[noble(gen_checked)]
+fn scale((factor, offset): (i32, i32), value: i32) -> i32 {
+    value * factor + offset
+}
+
+#[test]
+fn gen_checked_splits_safe_and_unsafe_entry_points() {
+    let checked: fn((i32, i32), i32) -> i32 = scale;
+    let unchecked: unsafe fn((i32, i32), i32) -> i32 = scale_unsafe;
+
+    assert_eq!(checked((2, 1), 5), 11);
+    assert_eq!(unsafe { unchecked((2, 1), 5) }, 11);
+}
+
+#[noble]
+fn offset_by(base: *const i32) -> impl Fn(i32) -> i32 {
+    // Unsafety is lexical: the closure body sits inside the wrapped block too
+    move |x| *base + x
+}
+
+#[test]
+fn returned_closure_body_is_wrapped() {
+    let base = 40;
+    let add = offset_by(&base);
+
+    assert_eq!(add(2), 42);
+}
+
+#[noble]
+fn first_negative(values: &[i32]) -> Option<usize> {
+    'search: {
+        for (index, value) in values.iter().enumerate() {
+            if *value < 0 {
+                break 'search Some(index);
+            }
+        }
+
+        None
+    }
+}
+
+#[test]
+fn labeled_block_breaks_with_value() {
+    assert_eq!(first_negative(&[3, 1, -4, 1]), Some(2));
+    assert_eq!(first_negative(&[2, 7]), None);
+}
+
+mod shadowing {
+    use noble::noble;
+
+    #[allow(dead_code)]
+    pub struct T;
+
+    #[noble]
+    pub fn identity<T>(t: T) -> T {
+        let same: T = t;
+        same
+    }
+}
+
+#[test]
+fn generic_names_shadow_module_items() {
+    assert_eq!(shadowing::identity(5u8), 5);
+    assert_eq!(shadowing::identity("crab"), "crab");
+}
+
+#[noble]
+#[unsafe(export_name = "noble_exported_answer")]
+pub extern "C" fn answer() -> i32 {
+    42
+}
+
+unsafe extern "C" {
+    // Only links if the wrapped function kept its custom symbol name
+    fn noble_exported_answer() -> i32;
+}
+
+#[test]
+fn export_name_survives_wrapping() {
+    assert_eq!(answer(), 42);
+    assert_eq!(unsafe { noble_exported_answer() }, 42);
+}
+
+// Exactly one of each pair survives cfg stripping, so the call below only
+// resolves if the conditional attributes applied the right way round
+#[noble]
+#[cfg_attr(test, inline)]
+#[cfg_attr(not(test), cfg(any()))]
+fn build_flavour() -> &'static str {
+    "test"
+}
+
+#[noble]
+#[cfg_attr(test, cfg(any()))]
+fn build_flavour() -> &'static str {
+    "release"
+}
+
+#[noble(gen_checked)]
+#[cfg_attr(test, inline)]
+#[cfg_attr(not(test), cfg(any()))]
+fn halve(value: u32) -> u32 {
+    value / 2
+}
+
+#[test]
+fn cfg_attr_survives_wrapping() {
+    assert_eq!(build_flavour(), "test");
+    assert_eq!(halve(8), 4);
+    assert_eq!(unsafe { halve_unsafe(8) }, 4);
+}
+
+#[noble(gen_checked)]
+#[track_caller]
+fn caller_line() -> u32 {
+    core::panic::Location::caller().line()
+}
+
+#[test]
+fn gen_checked_keeps_attributes_on_the_unchecked_half() {
+    assert_eq!(caller_line(), line!());
+    assert_eq!(unsafe { caller_line_unsafe() }, line!());
+}
+
+// Two `noble_triple` symbols would clash at link time, so only the safe half exports it
+#[noble(gen_checked)]
+#[unsafe(export_name = "noble_triple")]
+pub extern "C" fn triple(value: u32) -> u32 {
+    value * 3
+}
+
+unsafe extern "C" {
+    fn noble_triple(value: u32) -> u32;
+}
+
+// An `unsafe fn` can't be a test, so `#[test]` only registers the safe half
+#[noble(gen_checked)]
+#[test]
+fn gen_checked_exports_and_registers_only_the_safe_half() {
+    assert_eq!(unsafe { noble_triple(4) }, 12);
+    assert_eq!(unsafe { triple_unsafe(4) }, 12);
+}
+
+#[noble(emit_marker)]
+fn r#match(pattern: &str, text: &str) -> bool {
+    text.contains(pattern)
+}
+
+#[noble(emit_marker, gen_checked)]
+fn clamp_byte(value: i32) -> u8 {
+    value.clamp(0, 255) as u8
+}
+
+#[test]
+fn emit_marker_proves_the_attribute_ran() {
+    let markers = [_NOBLE_WRAPPED_MATCH, _NOBLE_WRAPPED_CLAMP_BYTE];
+
+    assert_eq!(markers, [true, true]);
+    assert!(r#match("ob", "noble"));
+    assert_eq!(clamp_byte(300), 255);
+}
+
+#[noble]
+fn make<const N: usize>() -> [u8; N] {
+    core::mem::zeroed()
+}
+
+#[noble(gen_checked)]
+fn repeat<T: Copy, const N: usize>(value: T) -> [T; N] {
+    [value; N]
+}
+
+#[test]
+fn const_generic_array_returns_survive() {
+    let empty: [u8; 0] = make();
+
+    assert_eq!(make::<16>().len(), 16);
+    assert_eq!(make::<3>(), [0, 0, 0]);
+    assert_eq!(empty.len(), 0);
+    assert_eq!(repeat::<char, 2>('x'), ['x', 'x']);
+    assert_eq!(unsafe { repeat_unsafe::<_, 4>(1.5) }.len(), 4);
+}
+
+#[noble]
+fn middle<'a, T: PartialOrd, const N: usize>(values: &'a [T; N]) -> &'a T
+where
+    [T; N]: Copy,
+{
+    values.get_unchecked(N / 2)
+}
+
+#[noble(gen_checked)]
+fn pick<'a, 'b: 'a, T, const N: usize>(left: &'a [T; N], right: &'b [T; N], index: usize) -> &'a T {
+    if index < N {
+        &left[index]
+    } else {
+        &right[index - N]
+    }
+}
+
+#[test]
+fn lifetime_type_and_const_parameters_survive() {
+    let odd = [3, 1, 4, 1, 5];
+    let letters = ['x', 'y'];
+
+    assert_eq!(*middle(&odd), 4);
+    assert_eq!(*middle::<char, 2>(&letters), 'y');
+    assert_eq!(*pick::<u8, 2>(&[1, 2], &[3, 4], 3), 4);
+    assert_eq!(unsafe { *pick_unsafe(&[1, 2], &[3, 4], 0) }, 1);
+}
+
+#[noble(unchecked_arith)]
+fn pack_rgb(r: u32, g: u32, b: u32, gamma: f32) -> (u32, f32) {
+    let red: u32 = r * 65_536;
+    let green = 256 * g;
+    // `green` has no declared type, so this `+` stays checked; float arithmetic does too
+    let shifted = red + green;
+    (shifted + b, gamma * 2.0 + 1.0)
+}
+
+#[test]
+fn unchecked_arith_keeps_results() {
+    assert_eq!(pack_rgb(0x12, 0x34, 0x56, 0.5), (0x12_3456, 2.0));
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Meters(u32);
+
+impl std::ops::Add<u32> for Meters {
+    type Output = Meters;
+
+    fn add(self, other: u32) -> Meters {
+        Meters(self.0 + other)
+    }
+}
+
+// None of these operands is provably a primitive integer, so every `+` keeps its own impl
+#[noble(unchecked_arith)]
+fn step(position: Meters, stride: u32) -> Meters {
+    let stride = Meters(stride);
+    let nudged = position + 1 + 2u32;
+
+    Meters((nudged + stride.0).0 + 1)
+}
+
+#[test]
+fn unchecked_arith_leaves_user_types_alone() {
+    assert_eq!(step(Meters(1), 10), Meters(15));
+}
+
+// An integer bound inside a block or closure is gone once it ends, so the outer `m + 1` is
+// still `Meters` addition
+#[noble(unchecked_arith)]
+fn shadowed_in_scopes(m: Meters) -> Meters {
+    {
+        let m: u32 = 1;
+        let _ = m + 1;
+    }
+    let double = |m: u32| m * 2;
+
+    m + 1 + double(3)
+}
+
+#[test]
+fn unchecked_arith_scopes_inner_bindings() {
+    assert_eq!(shadowed_in_scopes(Meters(1)), Meters(8));
+}
+
+// `arithmetic_overflow` rejects `max + one` at compile time but not `max.unchecked_add(one)`,
+// so this only builds because the rewrite happened. Calling it would be UB
+#[noble(unchecked_arith)]
+fn overflows_unless_rewritten() -> u8 {
+    let max: u8 = 255;
+    let one: u8 = 1;
+    max + one
+}
+
+#[test]
+fn unchecked_arith_rewrites_provable_integers() {
+    let _never_called: fn() -> u8 = overflows_unless_rewritten;
+}
+
+enum OpenError {
+    NotFound,
+    Denied,
+}
+
+impl From<OpenError> for i32 {
+    fn from(error: OpenError) -> i32 {
+        match error {
+            OpenError::NotFound => 2,
+            OpenError::Denied => 13,
+        }
+    }
+}
+
+fn check_access(path: &str) -> Result<(), OpenError> {
+    if path.starts_with("/root") {
+        return Err(OpenError::Denied);
+    }
+    Ok(())
+}
+
+#[noble(ffi_result)]
+fn open_config(path: *const u8, len: usize) -> Result<(), OpenError> {
+    let path = std::str::from_utf8_unchecked(std::slice::from_raw_parts(path, len));
+    check_access(path)?;
+
+    if !path.ends_with(".toml") {
+        return Err(OpenError::NotFound);
+    }
+    Ok(())
+}
+
+#[test]
+fn ffi_result_maps_to_status_codes() {
+    let status: extern "C" fn(*const u8, usize) -> i32 = open_config;
+    let call = |path: &str| status(path.as_ptr(), path.len());
+
+    assert_eq!(call("app.toml"), 0);
+    assert_eq!(call("app.json"), -2);
+    assert_eq!(call("/root/app.toml"), -13);
+}
+
+#[noble]
+fn checksum_of(bytes: impl IntoIterator<Item = u8>, seed: impl Into<u32>) -> u32 {
+    bytes.into_iter().fold(seed.into(), |sum, byte| {
+        sum.rotate_left(5) ^ u32::from(byte)
+    })
+}
+
+#[noble(gen_checked)]
+fn count_items(items: impl IntoIterator<Item = u8>) -> usize {
+    items.into_iter().count()
+}
+
+#[test]
+fn impl_trait_arguments_survive() {
+    let from_vec = checksum_of(vec![1, 2, 3], 0u8);
+    let from_array = checksum_of([1, 2, 3], 0u32);
+
+    assert_eq!(from_vec, from_array);
+    assert_eq!(count_items(*b"noble"), 5);
+    assert_eq!(unsafe { count_items_unsafe(0..4) }, 4);
+}
+
+// Cargo sets `CARGO_PKG_NAME` for every build, and nothing sets the other variable
+#[noble(env_gate = "CARGO_PKG_NAME", emit_marker)]
+fn gated_on(ptr: *const u8) -> u8 {
+    *ptr
+}
+
+#[noble(env_gate = "NOBLE_NEVER_SET_IN_TESTS", emit_marker)]
+fn gated_off(value: u8) -> u8 {
+    value
+}
+
+// Would clash with the marker if the gated-off item had been expanded
+const _NOBLE_WRAPPED_GATED_OFF: bool = false;
+
+// Not a Rust identifier, but still a name the environment can hold
+#[noble(env_gate = "NOBLE-NEVER-SET-IN-TESTS", emit_marker)]
+fn gated_on_dashes(value: u8) -> u8 {
+    value
+}
+
+const _NOBLE_WRAPPED_GATED_ON_DASHES: bool = false;
+
+#[test]
+fn env_gate_follows_the_environment() {
+    let markers = [_NOBLE_WRAPPED_GATED_ON, _NOBLE_WRAPPED_GATED_OFF];
+
+    assert_eq!(markers, [true, false]);
+    assert_eq!(gated_on(&3), 3);
+    assert_eq!(gated_off(4), 4);
+    assert_eq!(gated_on_dashes(5), 5);
+}
+
+#[noble]
+fn apply_to_each<F>(words: &[&str], transform: F) -> Vec<String>
+where
+    F: for<'a> Fn(&'a str) -> &'a str,
+{
+    words
+        .iter()
+        .map(|word| transform(std::str::from_utf8_unchecked(word.as_bytes())).to_owned())
+        .collect()
+}
+
+#[test]
+fn higher_ranked_bounds_survive_on_functions() {
+    assert_eq!(
+        apply_to_each(&["  crab ", "ferris"], str::trim),
+        ["crab", "ferris"]
+    );
+}
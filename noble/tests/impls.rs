@@ -0,0 +1,213 @@
+use noble::noble;
+
+struct Checksum<I> {
+    bytes: I,
+}
+
+#[noble]
+impl<I: Iterator<Item = u8>> Checksum<I> {
+    fn sum(self) -> u32 {
+        self.bytes.map(u32::from).sum()
+    }
+}
+
+#[test]
+fn impl_keeps_associated_type_bounds() {
+    let checksum = Checksum {
+        bytes: [1u8, 2, 3].into_iter(),
+    };
+
+    assert_eq!(checksum.sum(), 6);
+}
+
+struct Inventory {
+    counts: Vec<i32>,
+}
+
+#[noble]
+impl Inventory {
+    fn total_in_stock(&self) -> i32 {
+        if self.counts.is_empty() {
+            return -1;
+        }
+
+        // This `return` leaves the closure only, not the method
+        let in_stock = |count: &i32| {
+            if *count < 0 {
+                return 0;
+            }
+            *count
+        };
+
+        self.counts.iter().map(in_stock).sum()
+    }
+}
+
+#[test]
+fn returns_inside_closures_stay_local() {
+    let empty = Inventory { counts: Vec::new() };
+    let mixed = Inventory {
+        counts: vec![3, -2, 4],
+    };
+
+    assert_eq!(empty.total_in_stock(), -1);
+    assert_eq!(mixed.total_in_stock(), 7);
+}
+
+struct Ring<T> {
+    slots: Vec<T>,
+}
+
+#[noble(paired)]
+impl<T: Copy> Ring<T> {
+    fn get(&self, index: usize) -> T {
+        self.slots[index % self.slots.len()]
+    }
+
+    fn rotate<const N: usize>(&mut self) {
+        let len = self.slots.len();
+        self.slots.rotate_left(N % len);
+    }
+}
+
+#[test]
+fn paired_methods_behave_identically() {
+    let mut checked = Ring {
+        slots: vec![1, 2, 3],
+    };
+    let mut unchecked = Ring {
+        slots: vec![1, 2, 3],
+    };
+
+    checked.rotate::<1>();
+    unsafe { unchecked.rotate_unchecked::<1>() };
+
+    assert_eq!(checked.get(4), unsafe { unchecked.get_unchecked(4) });
+    assert_eq!(checked.slots, unchecked.slots);
+}
+
+struct Stack<T> {
+    items: Vec<T>,
+}
+
+#[noble(submodule = "raw")]
+impl<T: Clone> Stack<T> {
+    fn new() -> Self {
+        Self { items: Vec::new() }
+    }
+
+    fn push(&mut self, item: T) -> &mut Self {
+        self.items.push(item);
+        self
+    }
+
+    fn top(&self) -> T {
+        self.items.last().cloned().unwrap_unchecked()
+    }
+
+    fn take<const N: usize>(self) -> [T; N] {
+        std::array::from_fn(|index| self.items[index].clone())
+    }
+}
+
+#[test]
+fn submodule_forwards_to_the_methods() {
+    let mut stack: Stack<u8> = unsafe { raw::new() };
+    unsafe { raw::push(raw::push(&mut stack, 1), 2) };
+
+    assert_eq!(unsafe { raw::top(&stack) }, 2);
+    assert_eq!(stack.top(), 2);
+    assert_eq!(unsafe { raw::take::<_, 2>(stack) }, [1, 2]);
+}
+
+mod counters {
+    use noble::noble;
+
+    pub struct Counter {
+        pub(crate) count: u32,
+    }
+
+    #[noble(submodule = "raw")]
+    impl Counter {
+        pub fn bump(&mut self) -> u32 {
+            self.step(1)
+        }
+
+        pub(crate) fn reset(&mut self) {
+            self.count = 0;
+        }
+
+        fn step(&mut self, by: u32) -> u32 {
+            self.count += by;
+            self.count
+        }
+    }
+
+    pub fn step_twice(counter: &mut Counter) -> u32 {
+        unsafe { raw::step(counter, 2) }
+    }
+}
+
+#[test]
+fn submodule_functions_keep_their_method_visibility() {
+    let mut counter = counters::Counter { count: 0 };
+
+    assert_eq!(unsafe { counters::raw::bump(&mut counter) }, 1);
+    assert_eq!(counters::step_twice(&mut counter), 3);
+    unsafe { counters::raw::reset(&mut counter) };
+    assert_eq!(counter.count, 0);
+}
+
+struct Primes {
+    limit: u32,
+    sieve_runs: std::cell::Cell<u32>,
+    cache: Option<Vec<u32>>,
+}
+
+impl Primes {
+    #[noble(cache_through_shared)]
+    fn list(&self) -> Vec<u32> {
+        self.sieve_runs.set(self.sieve_runs.get() + 1);
+        (2..=self.limit)
+            .filter(|n| (2..*n).all(|d| n % d != 0))
+            .collect()
+    }
+}
+
+#[test]
+fn cache_through_shared_fills_the_cache_once() {
+    let primes = Primes {
+        limit: 20,
+        sieve_runs: std::cell::Cell::new(0),
+        cache: None,
+    };
+
+    assert_eq!(primes.list(), [2, 3, 5, 7, 11, 13, 17, 19]);
+    assert_eq!(primes.list().len(), 8);
+    assert_eq!(primes.sieve_runs.get(), 1);
+    assert!(primes.cache.is_some());
+}
+
+struct Trimmer<F> {
+    transform: F,
+}
+
+#[noble(submodule = "trimmer")]
+impl<F> Trimmer<F>
+where
+    F: for<'a> Fn(&'a str) -> &'a str,
+{
+    fn apply<'s>(&self, text: &'s str) -> &'s str {
+        (self.transform)(std::str::from_utf8_unchecked(text.as_bytes()))
+    }
+}
+
+#[test]
+fn higher_ranked_bounds_survive_on_impls() {
+    let trimmer = Trimmer {
+        transform: str::trim_start,
+    };
+
+    assert_eq!(trimmer.apply("  crab"), "crab");
+    assert_eq!(unsafe { trimmer::apply(&trimmer, "\tferris") }, "ferris");
+}
@@ -0,0 +1,53 @@
+use noble::noble;
+
+#[noble]
+mod ffi {
+    //! Everything in here is wrapped without annotating each item
+
+    use noble::noble;
+
+    pub fn read(ptr: *const i32) -> i32 {
+        *ptr
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub struct Span(pub usize, pub usize);
+
+    pub struct Cursor {
+        pub at: *const u8,
+    }
+
+    impl Cursor {
+        pub fn peek(&self) -> u8 {
+            *self.at
+        }
+    }
+
+    pub const LIMIT: usize = 4;
+
+    pub mod nested {
+        pub fn double(ptr: *const i32) -> i32 {
+            *ptr * 2
+        }
+    }
+
+    // Already annotated, so the module leaves it to its own attribute
+    #[noble(gen_checked)]
+    pub fn first(values: &[u8]) -> u8 {
+        *values.get_unchecked(0)
+    }
+}
+
+#[test]
+fn module_items_are_all_wrapped() {
+    let value = 21;
+    let bytes = [7u8, 8];
+    let cursor = unsafe { ffi::Cursor::new_unsafe(bytes.as_ptr()) };
+
+    assert_eq!(ffi::read(&value), 21);
+    assert_eq!(ffi::nested::double(&value), 42);
+    assert_eq!(cursor.peek(), 7);
+    assert_eq!(unsafe { ffi::Span::new_unsafe(1, 2) }, ffi::Span(1, 2));
+    assert_eq!(ffi::first(&bytes), 7);
+    assert_eq!(ffi::LIMIT, 4);
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "nightly")]
+#![feature(core_intrinsics)]
+#![allow(internal_features)]
+
+use noble::noble;
+
+#[noble(prefetch)]
+fn sum_strided(values: *const u64, len: usize, stride: usize) -> u64 {
+    (0..len)
+        .step_by(stride)
+        .map(|index| *values.add(index))
+        .sum()
+}
+
+#[test]
+fn prefetch_leaves_results_unchanged() {
+    let values: Vec<u64> = (1..=10).collect();
+
+    assert_eq!(
+        sum_strided(values.as_ptr(), values.len(), 3),
+        1 + 4 + 7 + 10
+    );
+}
+
+#[noble(intrinsics)]
+fn average(total: u32, count: u32) -> u32 {
+    if intrinsics::unlikely(count == 0) {
+        return 0;
+    }
+
+    intrinsics::assume(count != 0);
+    total / count
+}
+
+#[test]
+fn intrinsics_are_in_scope_and_callable() {
+    assert_eq!(average(12, 4), 3);
+    assert_eq!(average(12, 0), 0);
+}
+
+/// # Safety
+/// Nothing beyond what `#[noble]` already implies.
+#[noble]
+trait Source {
+    async fn fetch(&self) -> u8;
+
+    async fn fetch_twice(&self) -> u16 {
+        u16::from(self.fetch().await) * 2
+    }
+}
+
+struct Constant(u8);
+
+// Spelled out by hand so the test pins the qualifier order `#[noble]` has to match
+unsafe impl Source for Constant {
+    async unsafe fn fetch(&self) -> u8 {
+        self.0
+    }
+}
+
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+    let mut future = std::pin::pin!(future);
+
+    loop {
+        if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+#[test]
+fn async_trait_methods_become_async_unsafe_fn() {
+    let source = Constant(21);
+
+    assert_eq!(block_on(unsafe { source.fetch() }), 21);
+    assert_eq!(block_on(unsafe { source.fetch_twice() }), 42);
+}
+
+// Lives here because the dump is printed on every build that expands it
+#[noble(dump_layout)]
+#[repr(C)]
+struct Frame {
+    kind: u8,
+    length: u32,
+    crc: u16,
+}
+
+#[test]
+fn dump_layout_lists_offsets_size_and_align() {
+    assert_eq!(Frame::LAYOUT, [("kind", 0), ("length", 4), ("crc", 8)]);
+    assert_eq!((Frame::SIZE, Frame::ALIGN), (12, 4));
+}
@@ -0,0 +1,35 @@
+#![deny(static_mut_refs)]
+
+use noble::noble;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static INITIALIZED: AtomicUsize = AtomicUsize::new(0);
+
+fn expensive_table() -> Vec<u32> {
+    INITIALIZED.fetch_add(1, Ordering::SeqCst);
+    (0..4).map(|n| n * n).collect()
+}
+
+#[noble(lazy)]
+static SQUARES: Vec<u32> = expensive_table();
+
+#[test]
+fn lazy_initializes_once_on_first_access() {
+    assert_eq!(INITIALIZED.load(Ordering::SeqCst), 0);
+
+    assert_eq!(unsafe { SQUARES.get() }, &[0, 1, 4, 9]);
+    assert_eq!(unsafe { SQUARES.get()[3] }, 9);
+
+    assert_eq!(INITIALIZED.load(Ordering::SeqCst), 1);
+}
+
+#[noble(mutable_static)]
+static EVENTS: Vec<&'static str> = Vec::new();
+
+#[test]
+fn mutable_static_hands_out_exclusive_access() {
+    unsafe { events_mut() }.push("boot");
+    unsafe { events_mut() }.push("ready");
+
+    assert_eq!(unsafe { events_mut() }.as_slice(), ["boot", "ready"]);
+}
@@ -0,0 +1,393 @@
+#![cfg(feature = "std")]
+
+use noble::noble;
+use std::rc::{self, Rc};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[noble(catch_all)]
+extern "C" fn parse_port(text: *const u8, len: usize) -> u16 {
+    let bytes = std::slice::from_raw_parts(text, len);
+
+    std::str::from_utf8(bytes).unwrap().parse().unwrap()
+}
+
+#[test]
+fn catch_all_returns_the_default_instead_of_unwinding() {
+    let good = "8080";
+    let bad = "port";
+
+    assert_eq!(parse_port(good.as_ptr(), good.len()), 8080);
+    // An unwind out of an `extern "C"` fn would abort the whole test binary
+    assert_eq!(parse_port(bad.as_ptr(), bad.len()), 0);
+}
+
+thread_local! {
+    static FIB_RUNS: std::cell::Cell<u32> = const { std::cell::Cell::new(0) };
+}
+
+#[noble(memoize)]
+fn fib(n: u64) -> u64 {
+    FIB_RUNS.set(FIB_RUNS.get() + 1);
+
+    match n {
+        0 | 1 => n,
+        n => fib(n - 1) + fib(n - 2),
+    }
+}
+
+#[noble(memoize)]
+fn describe((name, age): (String, u8), shout: bool) -> String {
+    let text = format!("{} is {}", name, age);
+    if shout {
+        return text.to_uppercase();
+    }
+    text
+}
+
+#[test]
+fn memoize_runs_the_body_once_per_input() {
+    assert_eq!(fib(30), 832_040);
+    assert_eq!(FIB_RUNS.get(), 31);
+
+    assert_eq!(fib(30), 832_040);
+    assert_eq!(fib(12), 144);
+    assert_eq!(FIB_RUNS.get(), 31);
+
+    let ferris = (String::from("ferris"), 10);
+    assert_eq!(describe(ferris.clone(), true), "FERRIS IS 10");
+    assert_eq!(describe(ferris, false), "ferris is 10");
+}
+
+#[noble(abort_on_err)]
+fn read_level(text: &str) -> Result<u8, std::num::ParseIntError> {
+    text.parse()
+}
+
+#[test]
+fn abort_on_err_unwraps_ok() {
+    assert_eq!(read_level("7"), 7);
+}
+
+#[test]
+fn abort_on_err_aborts_the_process_on_err() {
+    // The child re-runs just this test, and is the one that aborts
+    if std::env::var_os("NOBLE_ABORT_ON_ERR_CHILD").is_some() {
+        read_level("eleventy");
+        return;
+    }
+
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args([
+            "abort_on_err_aborts_the_process_on_err",
+            "--exact",
+            "--nocapture",
+        ])
+        .env("NOBLE_ABORT_ON_ERR_CHILD", "1")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    #[cfg(unix)]
+    assert_eq!(
+        std::os::unix::process::ExitStatusExt::signal(&output.status),
+        Some(6)
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("read_level returned an error"));
+}
+
+#[noble(panic_to_errno)]
+fn checked_ratio(numerator: i32, denominator: i32) -> i32 {
+    if denominator == 0 {
+        panic!("ratio with a zero denominator");
+    }
+    numerator / denominator
+}
+
+#[test]
+fn panic_to_errno_records_the_message_for_last_error() {
+    assert!(unsafe { checked_ratio_last_error() }.is_null());
+    assert_eq!(checked_ratio(6, 3), 2);
+
+    assert_eq!(checked_ratio(1, 0), 0);
+    let message = unsafe { std::ffi::CStr::from_ptr(checked_ratio_last_error()) };
+    assert_eq!(message.to_str(), Ok("ratio with a zero denominator"));
+}
+
+#[noble(panic_to_errno)]
+mod parsers {
+    pub fn parse_count(text: &str) -> u32 {
+        text.parse().expect("count is not a number")
+    }
+
+    pub fn parse_ratio(text: &str) -> f32 {
+        text.parse().expect("ratio is not a number")
+    }
+}
+
+#[test]
+fn panic_to_errno_on_a_module_shares_one_last_error() {
+    assert!(unsafe { parsers::last_error() }.is_null());
+
+    assert_eq!(parsers::parse_count("x"), 0);
+    let message = unsafe { std::ffi::CStr::from_ptr(parsers::last_error()) };
+    assert_eq!(
+        message.to_str(),
+        Ok("count is not a number: ParseIntError { kind: InvalidDigit }")
+    );
+
+    assert_eq!(parsers::parse_ratio("y"), 0.0);
+    let message = unsafe { std::ffi::CStr::from_ptr(parsers::last_error()) };
+    assert_eq!(
+        message.to_str(),
+        Ok("ratio is not a number: ParseFloatError { kind: Invalid }")
+    );
+}
+
+#[noble(catch_and_return = "u16::MAX")]
+fn checked_div(numerator: u16, denominator: u16) -> u16 {
+    numerator / denominator
+}
+
+#[noble(catch_and_return = "Err(String::from(\"panicked\"))")]
+fn first_word(text: &str) -> Result<&str, String> {
+    Ok(text.split_whitespace().next().unwrap())
+}
+
+#[test]
+fn catch_and_return_evaluates_the_fallback_on_panic() {
+    assert_eq!(checked_div(10, 2), 5);
+    assert_eq!(checked_div(10, 0), u16::MAX);
+
+    assert_eq!(first_word("noble crab"), Ok("noble"));
+    assert_eq!(first_word("   "), Err(String::from("panicked")));
+}
+
+#[noble(timed)]
+fn find_even(values: &[u32]) -> Option<u32> {
+    for value in values {
+        if value % 2 == 0 {
+            return Some(*value);
+        }
+    }
+
+    None
+}
+
+#[test]
+fn timed_keeps_the_result_and_logs_every_return() {
+    assert_eq!(find_even(&[1, 4, 5]), Some(4));
+    assert_eq!(find_even(&[1, 3]), None);
+
+    // The log goes to stderr, which the test harness captures, so a child prints it for real
+    if std::env::var_os("NOBLE_TIMED_CHILD").is_some() {
+        return;
+    }
+
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args([
+            "timed_keeps_the_result_and_logs_every_return",
+            "--exact",
+            "--nocapture",
+        ])
+        .env("NOBLE_TIMED_CHILD", "1")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(stderr.matches("find_even took ").count(), 2);
+}
+
+struct Connection(std::rc::Rc<std::cell::Cell<bool>>);
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.0.set(true);
+    }
+}
+
+#[noble(leak_on_panic(connection))]
+fn send(connection: Connection, payload: &[u8]) -> usize {
+    if payload.is_empty() {
+        panic!("nothing to send");
+    }
+
+    assert!(!connection.0.get(), "sending on a closed connection");
+    payload.len()
+}
+
+#[test]
+fn leak_on_panic_skips_the_destructor_while_unwinding() {
+    let closed = std::rc::Rc::new(std::cell::Cell::new(false));
+
+    assert_eq!(send(Connection(closed.clone()), b"ping"), 4);
+    assert!(closed.get());
+
+    closed.set(false);
+    let unwound = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        send(Connection(closed.clone()), b"")
+    }));
+
+    assert!(unwound.is_err());
+    assert!(!closed.get());
+}
+
+#[cfg(unix)]
+#[noble(raw_fd)]
+struct Channel {
+    fd: std::os::fd::RawFd,
+    bytes_read: usize,
+}
+
+#[cfg(unix)]
+#[test]
+fn raw_fd_round_trips_a_pipe() {
+    use std::io::{Read, Write};
+    use std::os::fd::{AsRawFd, FromRawFd, IntoRawFd};
+
+    let (reader, mut writer) = std::io::pipe().unwrap();
+    writer.write_all(b"ping").unwrap();
+    drop(writer);
+
+    let fd = reader.into_raw_fd();
+    let mut channel = unsafe { Channel::from_raw_fd(fd) };
+    assert_eq!(channel.as_raw_fd(), fd);
+
+    let mut file = unsafe { std::fs::File::from_raw_fd(channel.as_raw_fd()) };
+    let mut received = String::new();
+    channel.bytes_read = file.read_to_string(&mut received).unwrap();
+
+    assert_eq!(received, "ping");
+    assert_eq!(channel.bytes_read, 4);
+}
+
+#[noble(io)]
+struct Scratch {
+    bytes: *mut u8,
+    len: usize,
+    pos: usize,
+}
+
+#[test]
+fn io_round_trips_bytes_within_capacity() {
+    use std::io::{Read, Write};
+
+    let mut storage = [0u8; 16];
+    let mut scratch = Scratch {
+        bytes: storage.as_mut_ptr(),
+        len: 0,
+        pos: 0,
+    };
+
+    scratch.write_all(b"crab").unwrap();
+    write!(scratch, "-{}", 42).unwrap();
+
+    let mut first = [0u8; 2];
+    scratch.read_exact(&mut first).unwrap();
+    let mut rest = String::new();
+    scratch.read_to_string(&mut rest).unwrap();
+
+    assert_eq!(&first, b"cr");
+    assert_eq!(rest, "ab-42");
+    assert_eq!(&storage[..7], b"crab-42");
+}
+
+#[test]
+fn io_reads_nothing_once_pos_is_past_len() {
+    use std::io::Read;
+
+    let mut storage = *b"crab";
+    let mut scratch = Scratch {
+        bytes: storage.as_mut_ptr(),
+        len: 2,
+        pos: 3,
+    };
+
+    assert_eq!(scratch.read(&mut [0u8; 4]).unwrap(), 0);
+    assert_eq!(scratch.pos, 3);
+}
+
+#[noble(as_vec)]
+#[repr(C)]
+struct RawBytes {
+    ptr: *mut u8,
+    len: usize,
+    cap: usize,
+}
+
+#[test]
+fn as_vec_round_trips_the_allocation() {
+    let mut bytes = Vec::with_capacity(16);
+    bytes.extend_from_slice(b"crab");
+
+    let raw = unsafe { RawBytes::from_vec(bytes) };
+    assert_eq!((raw.len, raw.cap), (4, 16));
+
+    let back = unsafe { raw.as_vec() };
+    assert_eq!(back, b"crab");
+    assert_eq!(back.capacity(), 16);
+}
+
+#[noble(box_leak)]
+struct Session {
+    user: String,
+}
+
+#[test]
+fn box_leak_round_trips_through_static() {
+    let leaked: &'static mut Session = Session {
+        user: String::from("ferris"),
+    }
+    .leak_static();
+    leaked.user.push_str("-the-crab");
+
+    let reclaimed = unsafe { Session::from_static_mut(leaked) };
+
+    assert_eq!(reclaimed.user, "ferris-the-crab");
+}
+
+#[noble(raw_waker)]
+struct WakeCounter {
+    wakes: AtomicUsize,
+}
+
+impl WakeCounter {
+    fn wake(&self) {
+        self.wakes.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn raw_waker_delegates_to_the_struct() {
+    let counter = Arc::new(WakeCounter {
+        wakes: AtomicUsize::new(0),
+    });
+
+    let waker = unsafe { Arc::clone(&counter).into_waker() };
+    let second = waker.clone();
+    waker.wake_by_ref();
+    second.wake();
+    drop(waker);
+
+    assert_eq!(counter.wakes.load(Ordering::SeqCst), 2);
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
+
+#[noble(weak_unchecked)]
+struct Observer {
+    subject: rc::Weak<u32>,
+}
+
+#[test]
+fn weak_unchecked_upgrades_a_live_reference() {
+    let subject = Rc::new(7);
+    let observer = Observer {
+        subject: Rc::downgrade(&subject),
+    };
+
+    let upgraded = unsafe { observer.subject_upgrade_unchecked() };
+
+    assert_eq!(*upgraded, 7);
+    assert_eq!(Rc::strong_count(&subject), 2);
+}
@@ -0,0 +1,1067 @@
+use noble::noble;
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::sync::atomic::Ordering;
+
+#[noble(raw_get)]
+struct Registry {
+    entries: HashMap<&'static str, u32>,
+}
+
+#[test]
+fn raw_get_reads_a_present_key() {
+    let registry = Registry {
+        entries: HashMap::from([("crab", 10), ("ferris", 6)]),
+    };
+
+    assert_eq!(unsafe { *registry.get_unchecked(&"ferris") }, 6);
+}
+
+#[noble(field_offset)]
+#[repr(C)]
+struct Header {
+    magic: u32,
+    r#type: u16,
+    length: u64,
+}
+
+#[test]
+fn field_offset_constants_follow_declaration_order() {
+    let offsets = [
+        Header::MAGIC_OFFSET,
+        Header::TYPE_OFFSET,
+        Header::LENGTH_OFFSET,
+    ];
+
+    assert!(offsets.is_sorted());
+    assert_eq!(offsets, [0, 4, 8]);
+}
+
+#[noble(packed_read)]
+#[repr(C, packed)]
+struct WireFrame {
+    tag: u8,
+    value: u32,
+}
+
+#[test]
+fn packed_read_copies_unaligned_fields() {
+    let frame = WireFrame {
+        tag: 1,
+        value: 0xDEAD_BEEF,
+    };
+
+    assert_eq!(unsafe { frame.read_tag() }, 1);
+    assert_eq!(unsafe { frame.read_value() }, 0xDEAD_BEEF);
+}
+
+#[noble(atomic_view)]
+struct Stats {
+    hits: u32,
+    label: &'static str,
+}
+
+#[test]
+fn atomic_view_updates_the_underlying_field() {
+    let stats = Stats {
+        hits: 41,
+        label: "cache",
+    };
+
+    let previous = unsafe { stats.hits_atomic() }.fetch_add(1, Ordering::SeqCst);
+
+    assert_eq!(previous, 41);
+    assert_eq!(stats.hits, 42);
+    assert_eq!(stats.label, "cache");
+}
+
+#[noble(default_zeroed)]
+struct Buffer<const N: usize> {
+    len: usize,
+    bytes: [u8; N],
+    scale: f32,
+}
+
+#[test]
+fn default_zeroed_produces_zeroed_fields() {
+    let buffer = Buffer::<4>::default();
+
+    assert_eq!(buffer.len, 0);
+    assert_eq!(buffer.bytes, [0; 4]);
+    assert_eq!(buffer.scale, 0.0);
+}
+
+mod markers {
+    /// # Safety
+    /// Any bit pattern is a valid value.
+    pub unsafe trait Plain {}
+
+    /// # Safety
+    /// All zeroes is a valid value.
+    pub unsafe trait AllZero {}
+}
+
+/// # Safety
+/// Any bit pattern is a valid value.
+unsafe trait Pod {}
+
+/// # Safety
+/// All zeroes is a valid value.
+unsafe trait Zeroable {}
+
+fn requires_pod<T: Pod + Zeroable>(value: T) -> T {
+    value
+}
+
+fn requires_plain<T: markers::Plain + markers::AllZero>(value: T) -> T {
+    value
+}
+
+#[noble(bytemuck_unsafe)]
+struct Vertex {
+    position: [f32; 3],
+}
+
+#[noble(bytemuck_unsafe(pod = "markers::Plain", zeroable = "markers::AllZero"))]
+struct Color(u8, u8, u8);
+
+#[test]
+fn bytemuck_unsafe_satisfies_marker_bounds() {
+    let vertex = requires_pod(Vertex {
+        position: [1.0, 2.0, 3.0],
+    });
+    let color = requires_plain(Color(255, 128, 0));
+
+    assert_eq!(vertex.position, [1.0, 2.0, 3.0]);
+    assert_eq!((color.0, color.1, color.2), (255, 128, 0));
+}
+
+#[noble(mutate_through_shared)]
+struct Shared {
+    value: u8,
+}
+
+#[test]
+fn mutate_through_shared_compiles() {
+    // Calling it is UB, so only its signature is checked
+    let _: unsafe fn(&Shared) -> &mut Shared = Shared::get_mut_unchecked;
+
+    assert_eq!(Shared { value: 1 }.value, 1);
+}
+
+#[noble(ffi_callback = "fn(i32) -> i32")]
+struct OnEvent<F> {
+    callback: F,
+    name: &'static str,
+}
+
+// Stands in for a C library that stores a callback plus its `void*` and calls it later
+extern "C" fn fire_event(
+    callback: unsafe extern "C" fn(*mut c_void, i32) -> i32,
+    userdata: *mut c_void,
+) -> i32 {
+    unsafe { callback(userdata, 20) + callback(userdata, 1) }
+}
+
+#[test]
+fn ffi_callback_trampolines_into_the_closure() {
+    let mut seen = Vec::new();
+    let mut handler = OnEvent {
+        callback: |event: i32| {
+            seen.push(event);
+            event * 2
+        },
+        name: "click",
+    };
+
+    let (callback, userdata) = handler.as_c_callback();
+    let result = fire_event(callback, userdata);
+
+    assert_eq!(result, 42);
+    assert_eq!(handler.name, "click");
+    assert_eq!(seen, [20, 1]);
+}
+
+#[noble]
+#[non_exhaustive]
+#[derive(Debug, PartialEq)]
+struct Version {
+    major: u16,
+    minor: u16,
+}
+
+#[test]
+fn non_exhaustive_structs_construct_in_crate() {
+    // `#[non_exhaustive]` only restricts other crates
+    let version = unsafe { Version::new_unsafe(1, 2) };
+
+    assert_eq!(version, Version { major: 1, minor: 2 });
+}
+
+#[noble(unchecked_index)]
+struct Samples<T> {
+    rate: u32,
+    data: Vec<T>,
+}
+
+#[test]
+fn unchecked_index_reads_and_writes_in_bounds() {
+    let mut samples = Samples {
+        rate: 44_100,
+        data: vec![0.25, 0.5, 0.75],
+    };
+
+    samples[1] = 1.0;
+
+    assert_eq!(samples[0], 0.25);
+    assert_eq!(samples[1], 1.0);
+    assert_eq!(samples.rate, 44_100);
+}
+
+#[noble(deref_ptr)]
+struct Handle {
+    id: u32,
+    raw: *const String,
+}
+
+#[test]
+fn deref_ptr_reads_through_the_stored_pointer() {
+    let target = String::from("socket");
+    let handle = Handle {
+        id: 3,
+        raw: &target,
+    };
+
+    assert_eq!(unsafe { handle.as_ref_unchecked() }, "socket");
+    assert_eq!(handle.id, 3);
+}
+
+#[noble(clone_bitwise)]
+#[derive(Debug, PartialEq)]
+struct Point3 {
+    x: i64,
+    y: i64,
+    z: i64,
+}
+
+#[test]
+fn clone_bitwise_copies_plain_data() {
+    let origin = Point3 { x: 1, y: -2, z: 3 };
+
+    assert_eq!(origin.clone(), origin);
+}
+
+#[repr(C)]
+struct Rgba {
+    r: u8,
+    g: u8,
+    b: u8,
+    a: u8,
+}
+
+#[noble(transmute_copy = "u16")]
+#[repr(C)]
+struct Sample {
+    left: u16,
+    right: u16,
+}
+
+#[test]
+fn transmute_copy_reads_a_smaller_prefix() {
+    let sample = Sample {
+        left: 0x1234,
+        right: 0xbeef,
+    };
+
+    assert_eq!(unsafe { sample.transmute_copy_to() }, 0x1234);
+    assert_eq!(sample.right, 0xbeef);
+}
+
+#[repr(C)]
+struct Celsius {
+    reading: f32,
+    sensor: u32,
+}
+
+#[noble(swap_with = "Celsius")]
+#[repr(C)]
+struct Fahrenheit {
+    reading: f32,
+    sensor: u32,
+}
+
+#[test]
+fn swap_with_exchanges_bytes_across_types() {
+    let mut celsius = Celsius {
+        reading: 21.5,
+        sensor: 1,
+    };
+    let mut fahrenheit = Fahrenheit {
+        reading: 70.7,
+        sensor: 2,
+    };
+
+    unsafe { fahrenheit.swap_with(&mut celsius) };
+
+    assert_eq!((celsius.reading, celsius.sensor), (70.7, 2));
+    assert_eq!((fahrenheit.reading, fahrenheit.sensor), (21.5, 1));
+}
+
+#[noble(cast_ref = "Rgba")]
+#[repr(C)]
+struct Pixel([u8; 4]);
+
+#[test]
+fn cast_ref_reads_through_the_other_layout() {
+    let pixel = Pixel([10, 20, 30, 255]);
+    let rgba = unsafe { pixel.as_other() };
+
+    assert_eq!((rgba.r, rgba.g, rgba.b, rgba.a), (10, 20, 30, 255));
+}
+
+#[noble(init_volatile)]
+#[repr(C)]
+struct UartRegisters {
+    control: u32,
+    baud_divisor: u16,
+    r#mod: u8,
+}
+
+#[test]
+fn init_volatile_writes_every_field() {
+    let mut registers = UartRegisters {
+        control: 0,
+        baud_divisor: 0,
+        r#mod: 0,
+    };
+
+    unsafe { registers.init(0b101, 26, 3) };
+
+    assert_eq!(registers.control, 0b101);
+    assert_eq!(registers.baud_divisor, 26);
+    assert_eq!(registers.r#mod, 3);
+}
+
+#[noble(try_transmute = "u32")]
+#[derive(Debug, PartialEq)]
+struct Ipv4([u8; 4]);
+
+#[noble(try_transmute = "u32")]
+#[derive(Debug)]
+struct Mac([u8; 6]);
+
+#[test]
+fn try_transmute_checks_sizes() {
+    let loopback = u32::from_ne_bytes([127, 0, 0, 1]);
+
+    assert_eq!(Ipv4::try_from(loopback), Ok(Ipv4([127, 0, 0, 1])));
+    assert_eq!(Mac::try_from(loopback).unwrap_err(), loopback);
+    assert_eq!(Mac([0; 6]).0.len(), 6);
+}
+
+#[noble(assume_aligned = 8)]
+struct ByteCursor {
+    at: *const u8,
+}
+
+#[test]
+fn assume_aligned_reads_through_an_aligned_buffer() {
+    let words = [0x1122_3344_5566_7788u64, 42];
+    let cursor = ByteCursor {
+        at: words.as_ptr().cast(),
+    };
+
+    let first = unsafe { *cursor.aligned_ptr::<u64>() };
+
+    assert_eq!(first, 0x1122_3344_5566_7788);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "misaligned")]
+fn assume_aligned_catches_misalignment_in_debug() {
+    let words = [0u64; 2];
+    let cursor = ByteCursor {
+        at: unsafe { words.as_ptr().cast::<u8>().add(1) },
+    };
+
+    let _ = unsafe { cursor.aligned_ptr::<u64>() };
+}
+
+#[noble(deref_ptr)]
+struct Ref<T> {
+    ptr: *const T,
+}
+
+#[test]
+fn generic_pointer_newtype_dereferences_as_t() {
+    let names = vec!["ferris", "corro"];
+    let by_ref: Ref<Vec<&str>> = unsafe { Ref::new_unsafe(&names) };
+
+    let seen: &Vec<&str> = unsafe { by_ref.as_ref_unchecked() };
+
+    assert_eq!(seen, &names);
+    assert_eq!(unsafe { *Ref::new_unsafe(&7u8).as_ref_unchecked() }, 7);
+}
+
+#[noble(utf8_unchecked)]
+struct Utf8Line {
+    bytes: Vec<u8>,
+    number: usize,
+}
+
+#[noble(utf8_unchecked)]
+struct BorrowedToken<'a>(&'a [u8]);
+
+#[test]
+fn utf8_unchecked_reads_valid_text() {
+    let line = Utf8Line {
+        bytes: "grüße".as_bytes().to_vec(),
+        number: 3,
+    };
+    let token = BorrowedToken(b"fn");
+
+    assert_eq!(unsafe { line.as_str_unchecked() }, "grüße");
+    assert_eq!(line.number, 3);
+    assert_eq!(unsafe { token.as_str_unchecked() }, "fn");
+}
+
+struct ShapeVtable {
+    area: unsafe fn(*mut ()) -> f64,
+    scale: unsafe fn(*mut (), f64),
+}
+
+struct Square {
+    side: f64,
+}
+
+static SQUARE_VTABLE: ShapeVtable = ShapeVtable {
+    area: |data| {
+        let square = unsafe { &*data.cast::<Square>() };
+        square.side * square.side
+    },
+    scale: |data, factor| unsafe { (*data.cast::<Square>()).side *= factor },
+};
+
+#[noble(dispatch(
+    vtable = "ShapeVtable",
+    methods(area = "fn() -> f64", scale = "fn(f64)")
+))]
+struct DynShape {
+    data: *mut (),
+    vtable: &'static ShapeVtable,
+}
+
+#[test]
+fn dispatch_calls_through_the_vtable() {
+    let mut square = Square { side: 3.0 };
+    let shape = DynShape {
+        data: (&raw mut square).cast(),
+        vtable: &SQUARE_VTABLE,
+    };
+
+    unsafe { shape.call_scale(2.0) };
+
+    assert_eq!(unsafe { shape.call_area() }, 36.0);
+}
+
+#[noble(uninit)]
+#[repr(C)]
+struct DeviceInfo {
+    id: u32,
+    label: String,
+}
+
+#[noble(uninit)]
+struct Pair<T>(T, T);
+
+#[test]
+fn uninit_fields_are_set_then_assumed_init() {
+    let mut info = DeviceInfo::uninit();
+    unsafe {
+        info.set_label(String::from("eth0"));
+        info.set_id(7);
+    }
+    let info: DeviceInfoInit = unsafe { info.assume_init() };
+
+    let mut pair = Pair::uninit();
+    unsafe {
+        pair.set_field_0('a');
+        pair.set_field_1('z');
+    }
+    let pair = unsafe { pair.assume_init() };
+
+    assert_eq!(info.id, 7);
+    assert_eq!(info.label, "eth0");
+    assert_eq!((pair.0, pair.1), ('a', 'z'));
+}
+
+/// `missing_docs` skips `#[doc(hidden)]` items, so this only builds while every
+/// generated (and undocumented) constructor is hidden
+#[deny(missing_docs)]
+pub mod hidden_api {
+    use noble::noble;
+
+    #[noble]
+    #[doc(hidden)]
+    pub struct Internal {
+        pub id: u32,
+    }
+
+    /// Documented itself, with everything noble adds kept out of the docs
+    #[noble(hide_generated, deref_ptr)]
+    pub struct Opaque {
+        ptr: *const u8,
+    }
+
+    /// Same for enums
+    #[noble(hide_generated)]
+    pub enum Mode {
+        /// Go fast
+        Fast,
+        /// Go slow
+        Slow,
+    }
+}
+
+#[test]
+fn hidden_constructors_still_work() {
+    use hidden_api::{Internal, Mode, Opaque};
+
+    let byte = 9u8;
+    let internal = unsafe { Internal::new_unsafe(1) };
+    let opaque = unsafe { Opaque::new_unsafe(&byte) };
+
+    assert_eq!(internal.id, 1);
+    assert_eq!(unsafe { *opaque.as_ref_unchecked() }, 9);
+    assert!(matches!(unsafe { Mode::new_fast_unsafe() }, Mode::Fast));
+    assert!(matches!(unsafe { Mode::new_slow_unsafe() }, Mode::Slow));
+}
+
+#[noble(atomic_ptr)]
+struct Slot {
+    head: *mut u32,
+    len: usize,
+}
+
+#[test]
+fn atomic_ptr_swaps_the_field_in_place() {
+    use std::sync::atomic::Ordering;
+
+    let mut old = 1u32;
+    let mut new = 2u32;
+    let slot = Slot {
+        head: &mut old,
+        len: 1,
+    };
+
+    let swapped = unsafe { slot.head_atomic() }.compare_exchange(
+        &raw mut old,
+        &raw mut new,
+        Ordering::AcqRel,
+        Ordering::Acquire,
+    );
+
+    assert!(swapped.is_ok());
+    assert_eq!(slot.head, &raw mut new);
+    assert_eq!(unsafe { *slot.head }, 2);
+    assert_eq!(slot.len, 1);
+}
+
+// A `$field:ty` fragment reaches the attribute as an invisible group around the pointer
+macro_rules! linked {
+    ($name:ident, $field:ty) => {
+        #[noble(atomic_ptr)]
+        struct $name {
+            next: $field,
+            prev: *const u32,
+        }
+    };
+}
+
+linked!(Link, *mut u32);
+
+#[test]
+fn atomic_ptr_sees_through_macro_fragments() {
+    let mut value = 3u32;
+    let link = Link {
+        next: core::ptr::null_mut(),
+        prev: core::ptr::null(),
+    };
+
+    unsafe { link.next_atomic() }.store(&raw mut value, std::sync::atomic::Ordering::Release);
+
+    assert_eq!(link.next, &raw mut value);
+    assert!(link.prev.is_null());
+}
+
+#[noble(addr_roundtrip)]
+struct Particle {
+    mass: f32,
+    charge: i8,
+}
+
+#[test]
+fn addr_roundtrip_reads_through_the_rebuilt_pointer() {
+    let particle = Particle {
+        mass: 1.5,
+        charge: -1,
+    };
+
+    let addr = unsafe { particle.charge_addr() };
+    let charge = unsafe { Particle::charge_from_addr(addr) };
+
+    assert_eq!(charge.cast_const(), &raw const particle.charge);
+    assert_eq!(unsafe { *charge }, -1);
+    assert_eq!(
+        unsafe { *Particle::mass_from_addr(particle.mass_addr()) },
+        1.5
+    );
+}
+
+#[noble(extend_lifetime)]
+struct Settings {
+    verbose: bool,
+}
+
+#[test]
+fn extend_lifetime_round_trips_a_leaked_value() {
+    let leaked: &'static Settings = Box::leak(Box::new(Settings { verbose: true }));
+    let again: &'static Settings = unsafe { leaked.as_ref_static() };
+
+    // Compiles for any borrow, which is exactly the footgun
+    let _launder: fn(&Settings) -> &'static Settings =
+        |settings| unsafe { settings.as_ref_static() };
+
+    assert!(std::ptr::eq(leaked, again));
+    assert!(again.verbose);
+}
+
+#[noble]
+#[derive(Debug, PartialEq)]
+struct Size {
+    width: u32,
+    height: u32,
+}
+
+#[noble]
+#[derive(Debug, PartialEq)]
+struct Point(i32, i32);
+
+#[noble]
+#[derive(Debug, PartialEq)]
+struct Marker;
+
+#[test]
+fn every_struct_shape_constructs() {
+    assert_eq!(
+        unsafe { Size::new_unsafe(3, 4) },
+        Size {
+            width: 3,
+            height: 4
+        }
+    );
+    assert_eq!(unsafe { Point::new_unsafe(-1, 2) }, Point(-1, 2));
+    assert_eq!(unsafe { Marker::new_unsafe() }, Marker);
+}
+
+#[repr(C)]
+struct CTimeval {
+    tv_sec: i64,
+    tv_usec: i64,
+}
+
+#[noble(validate_layout(size = 16, align = 8, like = "CTimeval"))]
+#[repr(C)]
+struct TimeVal {
+    seconds: i64,
+    micros: i64,
+}
+
+#[test]
+fn validate_layout_generates_a_passing_test() {
+    // The generated test also runs on its own; calling it proves it exists
+    time_val_layout_check();
+
+    let c = CTimeval {
+        tv_sec: 1,
+        tv_usec: 2,
+    };
+    let rust = TimeVal {
+        seconds: c.tv_sec,
+        micros: c.tv_usec,
+    };
+    assert_eq!(rust.seconds + rust.micros, 3);
+}
+
+#[noble(as_bytes)]
+#[repr(C)]
+struct Rgb565 {
+    bits: u16,
+    alpha: [u8; 2],
+}
+
+#[test]
+fn as_bytes_covers_the_whole_struct() {
+    let mut pixel = Rgb565 {
+        bits: u16::from_ne_bytes([0x12, 0x34]),
+        alpha: [0xff, 0x00],
+    };
+
+    assert_eq!(
+        unsafe { pixel.as_bytes() }.len(),
+        std::mem::size_of::<Rgb565>()
+    );
+    assert_eq!(unsafe { pixel.as_bytes() }, [0x12, 0x34, 0xff, 0x00]);
+
+    unsafe { pixel.as_bytes_mut()[3] = 0x80 };
+    assert_eq!(pixel.alpha, [0xff, 0x80]);
+    assert_eq!(pixel.bits.to_ne_bytes(), [0x12, 0x34]);
+}
+
+#[noble(linked_iter)]
+struct Node<T> {
+    value: T,
+    next: *mut Node<T>,
+}
+
+#[test]
+fn linked_iter_walks_the_chain() {
+    let mut tail = Node {
+        value: 'c',
+        next: std::ptr::null_mut(),
+    };
+    let mut middle = Node {
+        value: 'b',
+        next: &mut tail,
+    };
+    let head = Node {
+        value: 'a',
+        next: &mut middle,
+    };
+
+    let values: String = unsafe { head.linked_iter() }
+        .map(|node| node.value)
+        .collect();
+    let from_tail: Vec<char> = unsafe { tail.linked_iter() }
+        .map(|node| node.value)
+        .collect();
+
+    assert_eq!(values, "abc");
+    assert_eq!(from_tail, ['c']);
+}
+
+#[noble(split_mut)]
+struct Ledger {
+    entries: Vec<i64>,
+    balance: i64,
+}
+
+#[test]
+fn split_mut_borrows_both_fields() {
+    let mut ledger = Ledger {
+        entries: vec![5, -2],
+        balance: 0,
+    };
+
+    let (entries, balance) = unsafe { ledger.split_mut() };
+    entries.push(10);
+    *balance = entries.iter().sum();
+
+    assert_eq!(ledger.entries, [5, -2, 10]);
+    assert_eq!(ledger.balance, 13);
+}
+
+#[noble(reckless)]
+#[repr(C)]
+struct Telemetry {
+    ticks: u64,
+    errors: u32,
+    flags: u32,
+}
+
+#[test]
+fn reckless_bundles_the_dangerous_helpers() {
+    let mut telemetry = Telemetry::default();
+    assert_eq!(
+        (telemetry.ticks, telemetry.errors, telemetry.flags),
+        (0, 0, 0)
+    );
+
+    unsafe { telemetry.as_bytes_mut()[8] = 1 };
+    assert_eq!(unsafe { telemetry.as_bytes() }.len(), 16);
+    assert_eq!(telemetry.errors, u32::from_ne_bytes([1, 0, 0, 0]));
+
+    let _: fn(&Telemetry) -> &mut Telemetry = |t| unsafe { t.get_mut_unchecked() };
+    let _: fn(&Telemetry) -> &'static Telemetry = |t| unsafe { t.as_ref_static() };
+}
+
+#[noble(index_by_name)]
+struct Record {
+    id: u32,
+    r#type: &'static str,
+    score: f32,
+}
+
+#[test]
+fn index_by_name_points_at_the_field() {
+    let record = Record {
+        id: 7,
+        r#type: "user",
+        score: 0.5,
+    };
+
+    let id = unsafe { record.get_by_name("id") }.cast::<u32>();
+    let kind = unsafe { record.get_by_name("type") }.cast::<&str>();
+    let score = unsafe { record.get_by_name("score") }.cast::<f32>();
+
+    assert_eq!(unsafe { *id }, 7);
+    assert_eq!(unsafe { *kind }, "user");
+    assert_eq!(unsafe { *score }, 0.5);
+    assert!(unsafe { record.get_by_name("missing") }.is_null());
+}
+
+#[noble(deref_raw)]
+struct Ptr<T>(*const T);
+
+#[test]
+fn deref_raw_reaches_the_pointee() {
+    let greeting = String::from("hello");
+    let ptr = Ptr(&greeting);
+
+    assert_eq!(ptr.len(), 5);
+    assert!(ptr.starts_with("he"));
+    assert_eq!(*ptr, "hello");
+}
+
+#[noble(borrow_bytes)]
+#[repr(C)]
+struct RouteKey {
+    prefix: [u8; 3],
+    len: u8,
+}
+
+#[test]
+fn borrow_bytes_looks_up_byte_keyed_maps() {
+    use std::borrow::Borrow;
+    use std::collections::HashMap;
+
+    let routes = HashMap::from([(vec![10, 0, 0, 8], "lan"), (vec![192, 168, 1, 24], "home")]);
+    let key = RouteKey {
+        prefix: [192, 168, 1],
+        len: 24,
+    };
+
+    assert_eq!(routes.get::<[u8]>(key.borrow()), Some(&"home"));
+}
+
+#[noble(byte_array(size = 8))]
+#[repr(C)]
+struct Semver {
+    major: u16,
+    minor: u16,
+    patch: u32,
+}
+
+#[test]
+fn byte_array_round_trips_through_bytes() {
+    let semver = Semver {
+        major: 1,
+        minor: 2,
+        patch: 3,
+    };
+
+    let bytes = unsafe { semver.to_array() };
+    let expected: Vec<u8> = [1u16.to_ne_bytes(), 2u16.to_ne_bytes()]
+        .concat()
+        .into_iter()
+        .chain(3u32.to_ne_bytes())
+        .collect();
+    assert_eq!(bytes[..], expected[..]);
+
+    let back = unsafe { Semver::from_array(bytes) };
+    assert_eq!((back.major, back.minor, back.patch), (1, 2, 3));
+}
+
+struct DropCounter(std::rc::Rc<std::cell::Cell<u32>>);
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+#[noble(no_drop)]
+struct Pinned {
+    counter: DropCounter,
+    label: String,
+}
+
+#[test]
+fn no_drop_skips_destructors_until_asked() {
+    let drops = std::rc::Rc::new(std::cell::Cell::new(0));
+    let fields = || PinnedFields {
+        counter: DropCounter(drops.clone()),
+        label: String::from("pinned"),
+    };
+
+    {
+        let _pinned = Pinned::from_fields(fields());
+    }
+    assert_eq!(drops.get(), 0);
+
+    let mut pinned = Pinned::from_fields(fields());
+    unsafe { pinned.label_mut().push('!') };
+    assert_eq!(unsafe { pinned.label() }, "pinned!");
+    assert_eq!(unsafe { pinned.counter() }.0.get(), 0);
+
+    unsafe { pinned.drop_fields() };
+    assert_eq!(drops.get(), 1);
+
+    drop(unsafe { Pinned::from_fields(fields()).into_fields() });
+    assert_eq!(drops.get(), 2);
+}
+
+#[noble(ord_bytes)]
+#[repr(C)]
+#[derive(Debug)]
+struct Tag {
+    bytes: [u8; 3],
+    version: u8,
+}
+
+#[test]
+fn ord_bytes_sorts_lexicographically_by_bytes() {
+    let tag = |bytes: &[u8; 3], version| Tag {
+        bytes: *bytes,
+        version,
+    };
+    let mut tags = vec![
+        tag(b"zip", 1),
+        tag(b"abc", 9),
+        tag(b"abc", 2),
+        tag(b"mid", 0),
+    ];
+
+    tags.sort();
+
+    assert_eq!(
+        tags,
+        [
+            tag(b"abc", 2),
+            tag(b"abc", 9),
+            tag(b"mid", 0),
+            tag(b"zip", 1)
+        ]
+    );
+    assert_ne!(tag(b"abc", 2), tag(b"abc", 3));
+}
+
+#[noble(as_ref_raw)]
+struct Referent<T> {
+    target: *mut T,
+}
+
+fn shout(text: impl AsRef<String>) -> String {
+    text.as_ref().to_uppercase()
+}
+
+#[test]
+fn as_ref_raw_works_behind_generic_bounds() {
+    let mut name = String::from("ferris");
+    let mut referent = Referent {
+        target: &raw mut name,
+    };
+
+    referent.as_mut().push_str(" crab");
+    assert_eq!(shout(referent), "FERRIS CRAB");
+    assert_eq!(name, "ferris crab");
+}
+
+#[noble(send_sync_closure)]
+struct Job {
+    run: Box<dyn Fn(u32) -> u32>,
+    finish: Box<dyn FnOnce() -> String>,
+}
+
+#[test]
+fn send_sync_closure_crosses_threads() {
+    // `Rc` keeps the closures `!Send`, but each one is only ever touched by a single thread
+    let offset = std::rc::Rc::new(10);
+    let job = Job {
+        run: Box::new(move |value| value + *offset),
+        finish: Box::new(|| String::from("done")),
+    };
+    let job = unsafe { JobSendSync::new(job) };
+
+    let result = std::thread::spawn(move || (job.call_run(5), job.call_finish()))
+        .join()
+        .unwrap();
+
+    assert_eq!(result, (15, String::from("done")));
+}
+
+#[noble(union_cast)]
+struct Register {
+    float: f32,
+    bits: u32,
+    flag: u8,
+}
+
+#[test]
+fn union_cast_reinterprets_same_sized_fields() {
+    let register = Register {
+        float: 1.0,
+        bits: 0xc000_0000,
+        flag: 1,
+    };
+
+    assert_eq!(unsafe { register.float_as_bits() }, 0x3f80_0000);
+    assert_eq!(unsafe { register.bits_as_float() }, -2.0);
+    assert_eq!(register.flag, 1);
+}
+
+#[noble(inline_buffer(cap = 4))]
+struct Names<T> {
+    names: Vec<T>,
+}
+
+#[test]
+fn inline_buffer_pushes_and_reads_within_capacity() {
+    let heap = Names {
+        names: vec![String::from("ferris")],
+    };
+    let mut inline = NamesInline::<String>::new();
+
+    unsafe {
+        inline.push_unchecked(heap.names[0].clone());
+        inline.push_unchecked(String::from("corro"));
+    }
+
+    assert_eq!(NamesInline::<String>::CAPACITY, 4);
+    assert_eq!(inline.len(), 2);
+    assert_eq!(unsafe { inline.get_unchecked(1) }, "corro");
+    assert_eq!(inline.as_slice(), ["ferris", "corro"]);
+}
+
+#[noble(inline_buffer(cap = 2))]
+struct Tokens<'src, S> {
+    tokens: Vec<&'src str>,
+    source: S,
+}
+
+#[test]
+fn inline_buffer_handles_lifetimes_and_unused_parameters() {
+    let text = String::from("let x");
+    let heap = Tokens {
+        tokens: text.split(' ').collect(),
+        source: 7_u8,
+    };
+    let mut inline = TokensInline::<'_, u8>::new();
+
+    unsafe { inline.push_unchecked(heap.tokens[1]) };
+
+    assert!(!inline.is_empty());
+    assert_eq!(inline.as_slice(), ["x"]);
+    assert_eq!(heap.source, 7);
+}
@@ -0,0 +1,170 @@
+use noble::noble;
+
+/// # Safety
+/// Implementors only ever see pointers handed out by the vtable.
+#[noble(vtable)]
+trait Counter {
+    fn bump(&mut self, by: u32) -> u32;
+    fn get(&self) -> u32;
+}
+
+struct Ticks(u32);
+
+unsafe impl Counter for Ticks {
+    unsafe fn bump(&mut self, by: u32) -> u32 {
+        self.0 += by;
+        self.0
+    }
+
+    unsafe fn get(&self) -> u32 {
+        self.0
+    }
+}
+
+static TICKS_VTABLE: CounterVtable = CounterVtable::of::<Ticks>();
+
+#[test]
+fn vtable_dispatches_through_function_pointers() {
+    let mut ticks = Ticks(1);
+    let data = (&raw mut ticks).cast::<()>();
+
+    let counter = unsafe { CounterDyn::from_ptr(data, &TICKS_VTABLE) };
+
+    assert_eq!(unsafe { counter.bump(2) }, 3);
+    assert_eq!(unsafe { counter.get() }, 3);
+    assert_eq!(ticks.0, 3);
+}
+
+/// # Safety
+/// Nothing to uphold, everything is a default.
+#[noble(blanket)]
+trait Describe: std::fmt::Debug {
+    fn describe(&self) -> String {
+        format!("<{:?}>", self)
+    }
+}
+
+fn describe_all<T: Describe>(values: &[T]) -> Vec<String> {
+    values
+        .iter()
+        .map(|value| unsafe { value.describe() })
+        .collect()
+}
+
+#[test]
+fn blanket_implements_the_trait_for_any_type() {
+    assert_eq!(describe_all(&[1, 2]), ["<1>", "<2>"]);
+    assert_eq!(describe_all(&["crab"]), ["<\"crab\">"]);
+}
+
+/// # Safety
+/// Nothing beyond what `#[noble]` already implies.
+#[noble]
+trait Greet {
+    fn name(&self) -> String;
+
+    fn greeting(&self) -> String {
+        format!("hello, {}", self.name())
+    }
+}
+
+struct Polite;
+
+struct Terse;
+
+#[noble]
+impl Greet for Polite {
+    fn name(&self) -> String {
+        String::from("friend")
+    }
+}
+
+#[noble]
+impl Greet for Terse {
+    fn name(&self) -> String {
+        String::from("you")
+    }
+
+    fn greeting(&self) -> String {
+        format!("{}.", self.name())
+    }
+}
+
+#[test]
+fn impl_override_shadows_trait_default() {
+    assert_eq!(unsafe { Polite.greeting() }, "hello, friend");
+    assert_eq!(unsafe { Terse.greeting() }, "you.");
+}
+
+/// # Safety
+/// Nothing beyond what `#[noble]` already implies.
+#[noble(impl_for_refs)]
+trait Shape {
+    const SIDES: u32;
+
+    fn area(&self) -> f64;
+    fn scale(&mut self, by: f64);
+    fn unit() -> f64;
+}
+
+struct Square(f64);
+
+unsafe impl Shape for Square {
+    const SIDES: u32 = 4;
+
+    unsafe fn area(&self) -> f64 {
+        self.0 * self.0
+    }
+
+    unsafe fn scale(&mut self, by: f64) {
+        self.0 *= by;
+    }
+
+    unsafe fn unit() -> f64 {
+        1.0
+    }
+}
+
+fn grow_and_measure<S: Shape>(mut shape: S) -> (u32, f64, f64) {
+    unsafe {
+        shape.scale(3.0);
+        (S::SIDES, shape.area(), S::unit())
+    }
+}
+
+#[test]
+fn impl_for_refs_forwards_to_the_referent() {
+    let mut square = Square(2.0);
+
+    assert_eq!(grow_and_measure(&mut square), (4, 36.0, 1.0));
+    assert_eq!(square.0, 6.0);
+}
+
+/// # Safety
+/// Nothing beyond what `#[noble]` already implies.
+#[noble(impl_for_refs)]
+trait Label {
+    type Text: AsRef<str>;
+
+    fn label(&self) -> Self::Text;
+}
+
+unsafe impl Label for str {
+    type Text = String;
+
+    unsafe fn label(&self) -> String {
+        self.to_uppercase()
+    }
+}
+
+fn label_of<L: Label>(value: L) -> String {
+    unsafe { value.label() }.as_ref().to_owned()
+}
+
+#[test]
+fn impl_for_refs_covers_shared_references_and_unsized_targets() {
+    assert_eq!(label_of("crab"), "CRAB");
+
+    let boxed: Box<str> = "ferris".into();
+    assert_eq!(label_of(&*boxed), "FERRIS");
+}
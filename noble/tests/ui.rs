@@ -0,0 +1,10 @@
+#[test]
+fn ui() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+
+    // These pin output that only noble's `nightly` feature produces
+    if cfg!(feature = "nightly") {
+        cases.compile_fail("tests/ui/nightly/*.rs");
+    }
+}
@@ -0,0 +1,9 @@
+use noble::noble;
+
+#[noble(default_zeroed)]
+#[derive(Default)]
+struct Config {
+    retries: u32,
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+// `#[inline(never)]` is a hard error next to `#[unsafe(naked)]`, so the error below is
+// the proof it made it through the body rewrite
+use noble::noble;
+
+#[noble]
+#[inline(never)]
+#[unsafe(naked)]
+extern "C" fn hot_path() {
+    core::arch::naked_asm!("ret")
+}
+
+fn main() {
+    hot_path();
+}
@@ -0,0 +1,11 @@
+#![deny(unused_must_use)]
+
+use noble::noble;
+
+#[noble]
+#[must_use]
+struct Guard;
+
+fn main() {
+    unsafe { Guard::new_unsafe() };
+}
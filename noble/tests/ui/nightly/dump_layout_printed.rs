@@ -0,0 +1,14 @@
+// The dump is a note emitted while the macro expands, shown next to the build's other output
+use noble::noble;
+
+#[noble(dump_layout)]
+#[repr(C)]
+struct Header {
+    magic: [u8; 4],
+    version: u16,
+    flags: u32,
+}
+
+fn main() {
+    let _: () = Header::LAYOUT;
+}
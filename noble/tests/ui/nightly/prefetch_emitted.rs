@@ -0,0 +1,11 @@
+// The hint is an unstable intrinsic, so forgetting the feature gate shows it was emitted
+use noble::noble;
+
+#[noble(prefetch)]
+fn first(values: *const u8) -> u8 {
+    *values
+}
+
+fn main() {
+    first([7].as_ptr());
+}
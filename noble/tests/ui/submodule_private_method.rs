@@ -0,0 +1,21 @@
+mod counters {
+    use noble::noble;
+
+    pub struct Counter(u32);
+
+    #[noble(submodule = "raw")]
+    impl Counter {
+        pub fn get(&self) -> u32 {
+            self.0
+        }
+
+        fn set(&mut self, value: u32) {
+            self.0 = value;
+        }
+    }
+}
+
+fn main() {
+    let _ = counters::raw::get;
+    let _ = counters::raw::set;
+}